@@ -1,35 +1,45 @@
+use advent_of_code::{
+    error::ParseError,
+    helpers::{blocks, lines_of, parse_all, parse_num},
+    Input,
+};
+use nom::{combinator::map_opt, error::context};
 use std::collections::BinaryHeap;
 
+/** Parse the calories carried by every elf, and add them up */
+fn parse_elves(input: &Input) -> Result<Vec<u32>, ParseError> {
+    parse_all(
+        input,
+        blocks(context(
+            "calories that add up to less than 2^32",
+            map_opt(lines_of(parse_num::<u32>), |calories| {
+                calories
+                    .iter()
+                    .try_fold(0_u32, |total, calories| total.checked_add(*calories))
+            }),
+        )),
+    )
+}
+
 /** Find largest calorie count. Using basic sort (boring!) */
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut elves: Vec<u32> = vec![];
-    for elf_calories in input.split("\n\n") {
-        let mut total_calories = 0_u32;
-        for calories in elf_calories.split('\n') {
-            total_calories += calories.parse::<u32>().expect("Failed to parse calories");
-        }
-        elves.push(total_calories)
-    }
+pub fn part_one(input: &Input) -> Result<u32, ParseError> {
+    let mut elves = parse_elves(input)?;
     elves.sort();
 
-    Some(elves[elves.len() - 1])
+    Ok(elves[elves.len() - 1])
 }
 
 /** Find three largest calorie counts. Using binary heap this time! */
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut elf_heap: BinaryHeap<u32> = BinaryHeap::new();
-    for elf_calories in input.split("\n\n") {
-        let mut total_calories = 0_u32;
-        for calories in elf_calories.split('\n') {
-            total_calories += calories.parse::<u32>().expect("Failed to parse calories");
-        }
-        elf_heap.push(total_calories)
-    }
+pub fn part_two(input: &Input) -> Result<u32, ParseError> {
+    let mut elf_heap: BinaryHeap<u32> = parse_elves(input)?.into();
 
-    let top_three_sum =
-        elf_heap.pop().unwrap_or(0) + elf_heap.pop().unwrap_or(0) + elf_heap.pop().unwrap_or(0);
+    let top_three_sum = [elf_heap.pop(), elf_heap.pop(), elf_heap.pop()]
+        .into_iter()
+        .flatten()
+        .try_fold(0_u32, |sum, calories| sum.checked_add(calories))
+        .ok_or_else(|| ParseError::at(input, input, "calories that add up to less than 2^32"))?;
 
-    Some(top_three_sum)
+    Ok(top_three_sum)
 }
 
 fn main() {
@@ -41,16 +51,48 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 1);
-        assert_eq!(part_one(&input), Some(24000));
+        assert_eq!(part_one(&input), Ok(24000));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 1);
-        assert_eq!(part_two(&input), Some(45000));
+        assert_eq!(part_two(&input), Ok(45000));
+    }
+
+    fn random_calories(rng: &mut Rng) -> Vec<Vec<u32>> {
+        (0..rng.range(1..8))
+            .map(|_| {
+                (0..rng.range(1..5))
+                    .map(|_| rng.range(1..10000) as u32)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(1, parse_elves);
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_calories, |elves| {
+            let raw_elves: Vec<String> = elves
+                .iter()
+                .map(|calories| calories.iter().map(|c| c.to_string() + "\n").collect())
+                .collect();
+            let input = Input::new(&raw_elves.join("\n"));
+
+            let mut totals: Vec<u32> = elves.iter().map(|calories| calories.iter().sum()).collect();
+            totals.sort_by(|a, b| b.cmp(a));
+            assert_eq!(part_one(&input), Ok(totals[0]));
+            assert_eq!(part_two(&input), Ok(totals.iter().take(3).sum()));
+        });
     }
 }
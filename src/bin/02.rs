@@ -1,42 +1,54 @@
-#[derive(PartialEq, Eq)]
-enum MOVE {
+use advent_of_code::{
+    error::ParseError,
+    helpers::{lines_of, parse_all},
+    Input,
+};
+use nom::{
+    character::complete::{char, one_of},
+    combinator::map_opt,
+    error::context,
+    sequence::separated_pair,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
     Rock,
     Paper,
     Scissors,
 }
 
-impl MOVE {
-    fn will_beat(&self, opp_move: MOVE) -> bool {
+impl Move {
+    fn will_beat(&self, opp_move: Move) -> bool {
         match self {
-            MOVE::Rock => opp_move == MOVE::Scissors,
-            MOVE::Paper => opp_move == MOVE::Rock,
-            MOVE::Scissors => opp_move == MOVE::Paper,
+            Move::Rock => opp_move == Move::Scissors,
+            Move::Paper => opp_move == Move::Rock,
+            Move::Scissors => opp_move == Move::Paper,
         }
     }
-    fn get_winning_move(&self) -> MOVE {
+    fn get_winning_move(&self) -> Move {
         match self {
-            MOVE::Scissors => MOVE::Rock,
-            MOVE::Rock => MOVE::Paper,
-            MOVE::Paper => MOVE::Scissors,
+            Move::Scissors => Move::Rock,
+            Move::Rock => Move::Paper,
+            Move::Paper => Move::Scissors,
         }
     }
-    fn get_losing_move(&self) -> MOVE {
+    fn get_losing_move(&self) -> Move {
         match self {
-            MOVE::Rock => MOVE::Scissors,
-            MOVE::Paper => MOVE::Rock,
-            MOVE::Scissors => MOVE::Paper,
+            Move::Rock => Move::Scissors,
+            Move::Paper => Move::Rock,
+            Move::Scissors => Move::Paper,
         }
     }
     fn value(&self) -> u32 {
         match self {
-            MOVE::Rock => 1,
-            MOVE::Paper => 2,
-            MOVE::Scissors => 3,
+            Move::Rock => 1,
+            Move::Paper => 2,
+            Move::Scissors => 3,
         }
     }
 }
 
-fn calc_score(my_move: MOVE, opp_move: MOVE) -> u32 {
+fn calc_score(my_move: Move, opp_move: Move) -> u32 {
     if my_move == opp_move {
         my_move.value() + 3
     } else if my_move.will_beat(opp_move) {
@@ -46,53 +58,57 @@ fn calc_score(my_move: MOVE, opp_move: MOVE) -> u32 {
     }
 }
 
-fn input_to_move(c: &str) -> Option<MOVE> {
+fn input_to_move(c: char) -> Option<Move> {
     match c {
-        "A" | "X" => Some(MOVE::Rock),
-        "B" | "Y" => Some(MOVE::Paper),
-        "C" | "Z" => Some(MOVE::Scissors),
+        'A' | 'X' => Some(Move::Rock),
+        'B' | 'Y' => Some(Move::Paper),
+        'C' | 'Z' => Some(Move::Scissors),
         _ => None,
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
+/** Parse the rounds of the strategy guide: (opponent move, second column read as a move) */
+fn parse_rounds(input: &Input) -> Result<Vec<(Move, Move)>, ParseError> {
+    parse_all(
+        input,
+        lines_of(separated_pair(
+            context("A, B or C", map_opt(one_of("ABC"), input_to_move)),
+            char(' '),
+            context("X, Y or Z", map_opt(one_of("XYZ"), input_to_move)),
+        )),
+    )
+}
+
+pub fn part_one(input: &Input) -> Result<u32, ParseError> {
     let mut total_score = 0_u32;
-    for round in input.split('\n') {
-        if let Some((opp_input, player_input)) = round.split_once(' ') {
-            let opp_move = input_to_move(opp_input).expect("Failed to parse opponent move");
-            let my_move = input_to_move(player_input).expect("Failed to parse player move");
-            let score = calc_score(my_move, opp_move);
-            total_score += score;
-        }
+    for (opp_move, my_move) in parse_rounds(input)? {
+        let score = calc_score(my_move, opp_move);
+        total_score += score;
     }
 
-    Some(total_score)
+    Ok(total_score)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &Input) -> Result<u32, ParseError> {
     let mut total_score = 0_u32;
-    for round in input.split('\n') {
-        if let Some((opp_input, outcome)) = round.split_once(' ') {
-            let opp_move = input_to_move(opp_input).expect("Failed to parse opponent move");
-            match outcome {
-                "X" => {
-                    // need to lose
-                    total_score += calc_score(opp_move.get_losing_move(), opp_move)
-                }
-                "Y" => {
-                    // need to draw
-                    total_score += opp_move.value() + 3
-                }
-                "Z" => {
-                    // need to win
-                    total_score += calc_score(opp_move.get_winning_move(), opp_move)
-                }
-                _ => panic!("Didn't recognize outcome"),
+    for (opp_move, outcome) in parse_rounds(input)? {
+        match outcome {
+            Move::Rock => {
+                // X: need to lose
+                total_score += calc_score(opp_move.get_losing_move(), opp_move)
+            }
+            Move::Paper => {
+                // Y: need to draw
+                total_score += opp_move.value() + 3
+            }
+            Move::Scissors => {
+                // Z: need to win
+                total_score += calc_score(opp_move.get_winning_move(), opp_move)
             }
         }
     }
 
-    Some(total_score)
+    Ok(total_score)
 }
 
 fn main() {
@@ -104,16 +120,66 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 2);
-        assert_eq!(part_one(&input), Some(15));
+        assert_eq!(part_one(&input), Ok(15));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 2);
-        assert_eq!(part_two(&input), Some(12));
+        assert_eq!(part_two(&input), Ok(12));
+    }
+
+    fn random_rounds(rng: &mut Rng) -> Vec<(usize, usize)> {
+        (0..rng.range(1..20))
+            .map(|_| (rng.range(0..3), rng.range(0..3)))
+            .collect()
+    }
+
+    /// Outcome score of a round (0: lost, 3: draw, 6: won), by checking which shape beats which
+    fn reference_outcome(opp_shape: usize, my_shape: usize) -> u32 {
+        let beats = |a: usize, b: usize| (a + 3 - b) % 3 == 1;
+        if my_shape == opp_shape {
+            3
+        } else if beats(my_shape, opp_shape) {
+            6
+        } else {
+            0
+        }
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(2, parse_rounds);
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_rounds, |rounds| {
+            let input = Input::new(
+                &rounds
+                    .iter()
+                    .map(|&(opp, me)| format!("{} {}\n", b"ABC"[opp] as char, b"XYZ"[me] as char))
+                    .collect::<String>(),
+            );
+
+            let expected_one = rounds
+                .iter()
+                .map(|&(opp, me)| me as u32 + 1 + reference_outcome(opp, me));
+            assert_eq!(part_one(&input), Ok(expected_one.sum()));
+
+            // for part two the second column is the outcome: try the shapes until it matches
+            let expected_two = rounds.iter().map(|&(opp, outcome)| {
+                let me = (0..3)
+                    .find(|&me| reference_outcome(opp, me) == outcome as u32 * 3)
+                    .unwrap();
+                me as u32 + 1 + outcome as u32 * 3
+            });
+            assert_eq!(part_two(&input), Ok(expected_two.sum()));
+        });
     }
 }
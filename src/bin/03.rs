@@ -1,11 +1,17 @@
+use advent_of_code::{
+    error::ParseError,
+    helpers::{lines_of, parse_all},
+    Input,
+};
+use nom::{character::complete::alpha1, combinator::verify, error::context};
 use std::collections::HashSet;
 
 fn get_priority(c: char) -> Option<u32> {
     let ascii_value = c as u32;
-    if ascii_value >= 65 && ascii_value <= 90 {
+    if (65..=90).contains(&ascii_value) {
         return Some(ascii_value - 38);
     }
-    if ascii_value >= 97 && ascii_value <= 122 {
+    if (97..=122).contains(&ascii_value) {
         return Some(ascii_value - 96);
     }
     None
@@ -13,12 +19,9 @@ fn get_priority(c: char) -> Option<u32> {
 
 fn find_common_item_two(first_rucksack: &str, second_rucksack: &str) -> Option<char> {
     let item_hashset: HashSet<char> = first_rucksack.chars().collect();
-    for item in second_rucksack.chars() {
-        if item_hashset.contains(&item) {
-            return Some(item);
-        }
-    }
-    None
+    second_rucksack
+        .chars()
+        .find(|&item| item_hashset.contains(&item))
 }
 fn find_common_item_three(
     first_rucksack: &str,
@@ -28,34 +31,49 @@ fn find_common_item_three(
     let first_hashset: HashSet<char> = first_rucksack.chars().collect();
     let second_hashset: HashSet<char> = second_rucksack.chars().collect();
     let common_items: HashSet<&char> = first_hashset.intersection(&second_hashset).collect();
-    for item in third_rucksack.chars() {
-        if common_items.contains(&item) {
-            return Some(item);
-        }
-    }
-    None
+    third_rucksack
+        .chars()
+        .find(|&item| common_items.contains(&item))
+}
+
+/** Parse the rucksacks: one per line, with an even number of items (letters) */
+fn parse_rucksacks(input: &Input) -> Result<Vec<&str>, ParseError> {
+    parse_all(
+        input,
+        lines_of(context(
+            "an even number of items (a-z, A-Z)",
+            verify(alpha1, |items: &str| items.len().is_multiple_of(2)),
+        )),
+    )
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
+pub fn part_one(input: &Input) -> Result<u32, ParseError> {
     let mut total_priority = 0_u32;
-    for input_line in input.split('\n') {
-        let (first_rucksack, second_rucksack) = input_line.split_at(input_line.len() / 2);
-        let common_item =
-            find_common_item_two(first_rucksack, second_rucksack).expect("No common item found!");
-        total_priority += get_priority(common_item).expect("Couldn't calculate priority.");
+    for rucksack in parse_rucksacks(input)? {
+        let (first_rucksack, second_rucksack) = rucksack.split_at(rucksack.len() / 2);
+        let common_item = find_common_item_two(first_rucksack, second_rucksack)
+            .ok_or_else(|| ParseError::at(input, rucksack, "an item in both compartments"))?;
+        total_priority += get_priority(common_item).expect("items should be letters");
     }
-    Some(total_priority)
+    Ok(total_priority)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &Input) -> Result<u32, ParseError> {
     let mut total_priority = 0_u32;
-    let input_lines: Vec<&str> = input.split('\n').collect();
-    for rucksacks in input_lines.chunks(3) {
-        let common_item = find_common_item_three(rucksacks[0], rucksacks[1], rucksacks[2])
-            .expect("No common item found!");
-        total_priority += get_priority(common_item).expect("Couldn't calculate priority.");
+    let rucksacks = parse_rucksacks(input)?;
+    for group in rucksacks.chunks(3) {
+        let &[first, second, third] = group else {
+            return Err(ParseError::at(
+                input,
+                group[0],
+                "a group of three rucksacks",
+            ));
+        };
+        let common_item = find_common_item_three(first, second, third)
+            .ok_or_else(|| ParseError::at(input, first, "an item in all rucksacks of the group"))?;
+        total_priority += get_priority(common_item).expect("items should be letters");
     }
-    Some(total_priority)
+    Ok(total_priority)
 }
 
 fn main() {
@@ -67,16 +85,102 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 3);
-        assert_eq!(part_one(&input), Some(157));
+        assert_eq!(part_one(&input), Ok(157));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 3);
-        assert_eq!(part_two(&input), Some(70));
+        assert_eq!(part_two(&input), Ok(70));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(3, |input| part_one(input).and(part_two(input)));
+    }
+
+    const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    /// Rucksack with items from `pool`, where `common` is the only item in both compartments and
+    /// the badge is always included
+    fn random_rucksack(rng: &mut Rng, pool: &[u8], common: u8, badge: u8) -> String {
+        let mut others: Vec<u8> = pool.iter().copied().filter(|&i| i != common).collect();
+        rng.shuffle(&mut others);
+        let (left, right) = others.split_at(others.len() / 2);
+        let size = rng.range(2..10);
+        let compartments = [left, right].map(|items| {
+            let mut compartment = vec![common];
+            if items.contains(&badge) {
+                compartment.push(badge);
+            }
+            while compartment.len() < size {
+                compartment.push(*rng.choose(items));
+            }
+            rng.shuffle(&mut compartment);
+            compartment
+        });
+        String::from_utf8(compartments.concat()).unwrap()
+    }
+
+    /// Group of three rucksacks sharing exactly one item (the badge)
+    fn random_group(rng: &mut Rng) -> [String; 3] {
+        let badge = *rng.choose(ITEMS);
+        // every other item is left out of one of the rucksacks
+        let left_out: Vec<usize> = ITEMS.iter().map(|_| rng.range(0..3)).collect();
+        std::array::from_fn(|rucksack| {
+            let pool: Vec<u8> = ITEMS
+                .iter()
+                .zip(&left_out)
+                .filter(|(&item, &left_out)| item == badge || left_out != rucksack)
+                .map(|(&item, _)| item)
+                .collect();
+            let common = if rng.chance(1, 3) {
+                badge
+            } else {
+                *rng.choose(&pool)
+            };
+            random_rucksack(rng, &pool, common, badge)
+        })
+    }
+
+    fn reference_priority(item: u8) -> u32 {
+        ITEMS.iter().position(|&i| i == item).unwrap() as u32 + 1
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(
+            |rng| {
+                (0..rng.range(1..5))
+                    .flat_map(|_| random_group(rng))
+                    .collect::<Vec<_>>()
+            },
+            |rucksacks| {
+                let input = Input::new(&rucksacks.join("\n"));
+
+                // compare every item of the first compartment with every item of the second
+                let expected_one = rucksacks.iter().map(|rucksack| {
+                    let (first, second) = rucksack.as_bytes().split_at(rucksack.len() / 2);
+                    let common = first.iter().find(|item| second.contains(item)).unwrap();
+                    reference_priority(*common)
+                });
+                assert_eq!(part_one(&input), Ok(expected_one.sum()));
+
+                let expected_two = rucksacks.chunks(3).map(|group| {
+                    let badge = ITEMS.iter().find(|item| {
+                        group
+                            .iter()
+                            .all(|rucksack| rucksack.as_bytes().contains(item))
+                    });
+                    reference_priority(*badge.unwrap())
+                });
+                assert_eq!(part_two(&input), Ok(expected_two.sum()));
+            },
+        );
     }
 }
@@ -1,48 +1,55 @@
+use advent_of_code::{
+    error::ParseError,
+    helpers::{lines_of, parse_all, parse_num, ParseResult},
+    Input,
+};
+use nom::{character::complete::char, combinator::map, sequence::separated_pair};
+
 struct ElfRange {
     start: u32,
     end: u32,
 }
 
-fn get_parsed_range(range_input: &str) -> Option<ElfRange> {
-    if let Some((start_str, end_str)) = range_input.split_once('-') {
-        let parsed_start = start_str.parse::<u32>();
-        let parsed_end = end_str.parse::<u32>();
-        return match (parsed_start, parsed_end) {
-            (Ok(start), Ok(end)) => Some(ElfRange { start, end }),
-            _ => None,
-        };
-    }
-    None
+fn parse_range(input: &str) -> ParseResult<'_, ElfRange> {
+    map(
+        separated_pair(parse_num::<u32>, char('-'), parse_num::<u32>),
+        |(start, end)| ElfRange { start, end },
+    )(input)
+}
+
+/** Parse the section ranges of every pair of elves, like `2-4,6-8` */
+fn parse_elf_pairs(input: &Input) -> Result<Vec<[ElfRange; 2]>, ParseError> {
+    parse_all(
+        input,
+        lines_of(map(
+            separated_pair(parse_range, char(','), parse_range),
+            |(elf_1, elf_2)| [elf_1, elf_2],
+        )),
+    )
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
+pub fn part_one(input: &Input) -> Result<u32, ParseError> {
     let mut num_fully_contained = 0;
-    for elf_pair in input.split('\n') {
-        let (range_1, range_2) = elf_pair.split_once(',').unwrap();
-        let elf_1 = get_parsed_range(range_1).expect("Couldn't parse range");
-        let elf_2 = get_parsed_range(range_2).expect("Couldn't parse range");
+    for [elf_1, elf_2] in parse_elf_pairs(input)? {
         if (elf_1.start <= elf_2.start && elf_1.end >= elf_2.end)
             || (elf_2.start <= elf_1.start && elf_2.end >= elf_1.end)
         {
             num_fully_contained += 1;
         }
     }
-    Some(num_fully_contained)
+    Ok(num_fully_contained)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &Input) -> Result<u32, ParseError> {
     let mut num_partially_contained = 0;
-    for elf_pair in input.split('\n') {
-        let (range_1, range_2) = elf_pair.split_once(',').unwrap();
-        let elf_1 = get_parsed_range(range_1).expect("Couldn't parse range");
-        let elf_2 = get_parsed_range(range_2).expect("Couldn't parse range");
+    for [elf_1, elf_2] in parse_elf_pairs(input)? {
         if (elf_1.start <= elf_2.start && elf_1.end >= elf_2.start)
             || (elf_2.start <= elf_1.start && elf_2.end >= elf_1.start)
         {
             num_partially_contained += 1;
         }
     }
-    Some(num_partially_contained)
+    Ok(num_partially_contained)
 }
 
 fn main() {
@@ -54,16 +61,61 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 4);
-        assert_eq!(part_one(&input), Some(2));
+        assert_eq!(part_one(&input), Ok(2));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 4);
-        assert_eq!(part_two(&input), Some(4));
+        assert_eq!(part_two(&input), Ok(4));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(4, parse_elf_pairs);
+    }
+
+    fn random_pairs(rng: &mut Rng) -> Vec<[(u32, u32); 2]> {
+        let random_range = |rng: &mut Rng| {
+            let start = rng.range(1..20) as u32;
+            (start, start + rng.range(0..10) as u32)
+        };
+        (0..rng.range(1..20))
+            .map(|_| [random_range(rng), random_range(rng)])
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_pairs, |pairs| {
+            let input = Input::new(
+                &pairs
+                    .iter()
+                    .map(|[(a, b), (c, d)]| format!("{a}-{b},{c}-{d}\n"))
+                    .collect::<String>(),
+            );
+
+            // compare the sections one by one
+            let sections = |(start, end): (u32, u32)| (start..=end).collect::<Vec<_>>();
+            let is_subset = |a: &[u32], b: &[u32]| a.iter().all(|section| b.contains(section));
+            let expected_one = pairs.iter().filter(|[first, second]| {
+                let (first, second) = (sections(*first), sections(*second));
+                is_subset(&first, &second) || is_subset(&second, &first)
+            });
+            assert_eq!(part_one(&input), Ok(expected_one.count() as u32));
+
+            let expected_two = pairs.iter().filter(|[first, second]| {
+                let second = sections(*second);
+                sections(*first)
+                    .iter()
+                    .any(|section| second.contains(section))
+            });
+            assert_eq!(part_two(&input), Ok(expected_two.count() as u32));
+        });
     }
 }
@@ -1,113 +1,159 @@
+use advent_of_code::{
+    error::ParseError,
+    helpers::{lines_of, parse_num, ParseResult},
+    Input,
+};
+use nom::{
+    bytes::complete::tag,
+    combinator::{all_consuming, consumed, map},
+    sequence::{preceded, tuple},
+};
 use std::collections::{HashMap, VecDeque};
 
-pub fn part_one(input: &str) -> Option<String> {
-    let (raw_stacks, raw_instructions) = input.split_once("\n\n").unwrap();
+/// The stacks of crates by their number, with the top crate at the front
+type Stacks = HashMap<u8, VecDeque<char>>;
 
+struct Instruction<'a> {
+    num_crates_to_move: u32,
+    from_stack_id: u8,
+    to_stack_id: u8,
+    /** the instruction as written in the input, to point at it in errors */
+    raw: &'a str,
+}
+
+impl Instruction<'_> {
+    fn not_enough_crates(&self, input: &str) -> ParseError {
+        ParseError::at(input, self.raw, "a move of at most the crates on the stack")
+    }
+}
+
+pub fn part_one(input: &Input) -> Result<String, ParseError> {
     // Create a hashmap representing the stacks. Each stack is stored as a 'VecDeque' so
     // we can easily add/remove from the front and back as needed
-    let mut stacks = create_stack_hashmap(raw_stacks);
+    let (mut stacks, instructions) = parse_input(input)?;
 
-    // Parse the instructions and move the stacks
-    let instructions = parse_instructions(raw_instructions);
-    for (num_crates_to_move, from_stack_id, to_stack_id) in instructions {
-        for _ in 0..num_crates_to_move {
-            let moved_crate = stacks.get_mut(&from_stack_id).unwrap().pop_front().unwrap();
+    // Move the stacks according to the instructions
+    for instruction in instructions {
+        for _ in 0..instruction.num_crates_to_move {
+            let moved_crate = stacks
+                .get_mut(&instruction.from_stack_id)
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| instruction.not_enough_crates(input))?;
             stacks
-                .get_mut(&to_stack_id)
+                .get_mut(&instruction.to_stack_id)
                 .unwrap()
                 .push_front(moved_crate);
         }
     }
 
-    // get the top crate of every stack and return it as a string
-    let mut top_crates = String::new();
-    for stack_id in 1_u8..=stacks.len().try_into().unwrap() {
-        let stack = stacks.get_mut(&stack_id).unwrap();
-        top_crates.push(stack.pop_front().unwrap_or_default());
-    }
-
-    Some(top_crates)
+    Ok(top_crates(stacks))
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    let (raw_stacks, raw_instructions) = input.split_once("\n\n").unwrap();
-
-    let mut stacks = create_stack_hashmap(raw_stacks);
-    let instructions = parse_instructions(raw_instructions);
+pub fn part_two(input: &Input) -> Result<String, ParseError> {
+    let (mut stacks, instructions) = parse_input(input)?;
 
     // We'll move the stacks as a group this time!
-    for (num_crates_to_move, from_stack_id, to_stack_id) in instructions {
+    for instruction in instructions {
         let mut crates_to_move: Vec<char> = vec![];
-        for _ in 0..num_crates_to_move {
-            let moved_crate = stacks.get_mut(&from_stack_id).unwrap().pop_front().unwrap();
+        for _ in 0..instruction.num_crates_to_move {
+            let moved_crate = stacks
+                .get_mut(&instruction.from_stack_id)
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| instruction.not_enough_crates(input))?;
             crates_to_move.push(moved_crate)
         }
-        for _ in 0..num_crates_to_move {
+        for moved_crate in crates_to_move.into_iter().rev() {
             stacks
-                .get_mut(&to_stack_id)
+                .get_mut(&instruction.to_stack_id)
                 .unwrap()
-                .push_front(crates_to_move.pop().unwrap());
+                .push_front(moved_crate);
         }
     }
 
-    // get the top crate of every stack and return it as a string
+    Ok(top_crates(stacks))
+}
+
+/// Get the top crate of every stack and return it as a string
+fn top_crates(mut stacks: Stacks) -> String {
     let mut top_crates = String::new();
     for stack_id in 1_u8..=stacks.len().try_into().unwrap() {
-        let stack = stacks.get_mut(&stack_id).unwrap();
-        top_crates.push(stack.pop_front().unwrap_or_default());
+        // stacks that were emptied by the moves are skipped
+        if let Some(top_crate) = stacks.get_mut(&stack_id).unwrap().pop_front() {
+            top_crates.push(top_crate);
+        }
     }
+    top_crates
+}
+
+/// Parse the drawing of the stacks and the rearrangement procedure. Every instruction is checked
+/// to refer to existing stacks.
+fn parse_input(input: &Input) -> Result<(Stacks, Vec<Instruction<'_>>), ParseError> {
+    let mut blocks = input.blocks();
+    let raw_stacks = blocks
+        .next()
+        .ok_or_else(|| ParseError::at(input, input, "a drawing of the stacks"))?;
+    let stacks = create_stack_hashmap(input, raw_stacks)?;
 
-    Some(top_crates)
+    let instructions = match blocks.next() {
+        Some(raw_instructions) => {
+            all_consuming(lines_of(parse_instruction))(raw_instructions)
+                .map_err(|e| ParseError::from_nom(input, e))?
+                .1
+        }
+        None => vec![],
+    };
+    if let Some(rest) = blocks.next() {
+        return Err(ParseError::at(input, rest, "end of input"));
+    }
+    for instruction in &instructions {
+        for stack_id in [instruction.from_stack_id, instruction.to_stack_id] {
+            if !stacks.contains_key(&stack_id) {
+                return Err(ParseError::at(input, instruction.raw, "existing stacks"));
+            }
+        }
+    }
+    Ok((stacks, instructions))
 }
 
-fn create_stack_hashmap(raw_stacks: &str) -> HashMap<u8, VecDeque<char>> {
-    let mut stacks: HashMap<u8, VecDeque<char>> = HashMap::new();
-    for stack_row in raw_stacks.split('\n') {
-        for (stack_num, raw_crate) in stack_row
-            .chars()
-            .collect::<Vec<char>>()
-            .chunks(4)
-            .enumerate()
-        {
-            let crate_char = raw_crate[1];
-            if crate_char >= 'A' && crate_char <= 'Z' {
-                let stack = stacks
-                    .entry((stack_num + 1).try_into().unwrap())
-                    .or_default();
-                stack.push_back(raw_crate[1]);
+/// Create the stacks from the drawing, where every stack takes four characters per row. Every
+/// stack that's drawn gets an entry, even if it's empty.
+fn create_stack_hashmap(input: &str, raw_stacks: &str) -> Result<Stacks, ParseError> {
+    let mut stacks = Stacks::new();
+    for stack_row in raw_stacks.lines() {
+        let row_chars: Vec<(usize, char)> = stack_row.char_indices().collect();
+        for (stack_num, raw_crate) in row_chars.chunks(4).enumerate() {
+            let position = &stack_row[raw_crate[0].0..];
+            let stack_id = u8::try_from(stack_num + 1)
+                .map_err(|_| ParseError::at(input, position, "at most 255 stacks"))?;
+            let stack = stacks.entry(stack_id).or_default();
+            match raw_crate.get(1).map(|(_, c)| *c) {
+                Some(crate_char) if crate_char.is_ascii_uppercase() => stack.push_back(crate_char),
+                // no crate here, or the row with the stack numbers
+                Some(c) if c == ' ' || c.is_ascii_digit() => (),
+                _ => return Err(ParseError::at(input, position, "a crate like [A]")),
             }
         }
     }
-    stacks
+    Ok(stacks)
 }
 
-fn parse_instructions(raw_instructions: &str) -> Vec<(u32, u8, u8)> {
-    let mut instructions: Vec<(u32, u8, u8)> = vec![];
-    for raw_instruction in raw_instructions.split('\n') {
-        let mut instr_parts = raw_instruction
-            .strip_prefix("move ")
-            .unwrap()
-            .split(" from ");
-        let num_crates_to_move: u32 = instr_parts
-            .next()
-            .unwrap()
-            .parse()
-            .expect("Couldn't parse # crates");
-        let mut raw_stack_ids = instr_parts.next().unwrap().split(" to ");
-        let from_stack_id: u8 = raw_stack_ids
-            .next()
-            .unwrap()
-            .parse()
-            .expect("Couldn't parse 'from' stack");
-        let to_stack_id: u8 = raw_stack_ids
-            .next()
-            .unwrap()
-            .parse()
-            .expect("Couldn't parse 'to' stack");
-
-        instructions.push((num_crates_to_move, from_stack_id, to_stack_id));
-    }
-    instructions
+/// Parse an instruction like `move 1 from 2 to 1`
+fn parse_instruction(input: &str) -> ParseResult<'_, Instruction<'_>> {
+    map(
+        consumed(tuple((
+            preceded(tag("move "), parse_num::<u32>),
+            preceded(tag(" from "), parse_num::<u8>),
+            preceded(tag(" to "), parse_num::<u8>),
+        ))),
+        |(raw, (num_crates_to_move, from_stack_id, to_stack_id))| Instruction {
+            num_crates_to_move,
+            from_stack_id,
+            to_stack_id,
+            raw,
+        },
+    )(input)
 }
 
 fn main() {
@@ -119,16 +165,113 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 5);
-        assert_eq!(part_one(&input), Some(String::from("CMZ")));
+        assert_eq!(part_one(&input), Ok(String::from("CMZ")));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 5);
-        assert_eq!(part_two(&input), Some(String::from("MCD")));
+        assert_eq!(part_two(&input), Ok(String::from("MCD")));
+    }
+
+    #[test]
+    fn test_emptied_stacks() {
+        let input = Input::new("[A] [B]\n 1   2 \n\nmove 1 from 1 to 2\n");
+        assert_eq!(part_one(&input), Ok(String::from("A")));
+        assert_eq!(part_two(&input), Ok(String::from("A")));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(5, |input| part_one(input).and(part_two(input)));
+    }
+
+    #[derive(Debug)]
+    struct Procedure {
+        stacks: Vec<Vec<char>>,
+        moves: Vec<(usize, usize, usize)>,
+    }
+
+    fn random_procedure(rng: &mut Rng) -> Procedure {
+        let mut stacks: Vec<Vec<char>> = (0..rng.range(1..10))
+            .map(|_| {
+                (0..rng.range(1..6))
+                    .map(|_| char::from(b'A' + rng.range(0..26) as u8))
+                    .collect()
+            })
+            .collect();
+        let initial_stacks = stacks.clone();
+        let mut moves = vec![];
+        for _ in 0..rng.range(0..20) {
+            let from = rng.range(0..stacks.len());
+            let to = rng.range(0..stacks.len());
+            if from == to || stacks[from].is_empty() {
+                continue;
+            }
+            let count = rng.range(1..stacks[from].len() + 1);
+            let remaining = stacks[from].len() - count;
+            let moved: Vec<char> = stacks[from].drain(remaining..).collect();
+            stacks[to].extend(moved);
+            moves.push((count, from, to));
+        }
+        Procedure {
+            stacks: initial_stacks,
+            moves,
+        }
+    }
+
+    fn procedure_input(procedure: &Procedure) -> Input {
+        let height = procedure.stacks.iter().map(Vec::len).max().unwrap();
+        let mut input = String::new();
+        for level in (0..height).rev() {
+            let row: Vec<String> = procedure
+                .stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("[{c}]"),
+                    None => "   ".to_string(),
+                })
+                .collect();
+            input += &(row.join(" ") + "\n");
+        }
+        let numbers: Vec<String> = (1..=procedure.stacks.len())
+            .map(|id| format!(" {id} "))
+            .collect();
+        input += &(numbers.join(" ") + "\n\n");
+        for (count, from, to) in &procedure.moves {
+            input += &format!("move {count} from {} to {}\n", from + 1, to + 1);
+        }
+        Input::new(&input)
+    }
+
+    /// Move the crates one at a time (part one) or all at once (part two) on plain vectors, with
+    /// the top of each stack at the end
+    fn reference_top_crates(procedure: &Procedure, all_at_once: bool) -> String {
+        let mut stacks = procedure.stacks.clone();
+        for &(count, from, to) in &procedure.moves {
+            let mut moved = vec![];
+            for _ in 0..count {
+                moved.push(stacks[from].pop().unwrap());
+            }
+            if all_at_once {
+                moved.reverse();
+            }
+            stacks[to].extend(moved);
+        }
+        stacks.iter().filter_map(|stack| stack.last()).collect()
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_procedure, |procedure| {
+            let input = procedure_input(procedure);
+            assert_eq!(part_one(&input), Ok(reference_top_crates(procedure, false)));
+            assert_eq!(part_two(&input), Ok(reference_top_crates(procedure, true)));
+        });
     }
 }
@@ -1,41 +1,50 @@
 // Thanks to fasterthanlime for this idea using bitwise operations: https://fasterthanli.me/series/advent-of-code-2022/part-6
 
-pub fn part_one(input: &str) -> Option<usize> {
+use advent_of_code::{
+    error::{ParseError, PuzzleError},
+    Input,
+};
+
+/// Parse the datastream, which should only contain lowercase letters
+fn parse_datastream(input: &Input) -> Result<&[u8], ParseError> {
+    match input.char_indices().find(|(_, c)| !c.is_ascii_lowercase()) {
+        Some((position, _)) => Err(ParseError::at(input, &input[position..], "a letter (a-z)")),
+        None => Ok(input.as_bytes()),
+    }
+}
+
+pub fn part_one(input: &Input) -> Result<usize, PuzzleError> {
     // We want to identify the first 'marker' i.e. the first consecutive sequence of four different characters
-    let first_marker_index = input
-        .as_bytes()
+    let first_marker_index = parse_datastream(input)?
         .windows(4) // look at windows of four characters
         .map(|window| {
             window
                 .iter()
-                .map(|c| {
-                    assert!(c.is_ascii_lowercase()); // all letters should be ASCII lowercase
-                    (1 << (*c as u32 - 'a' as u32)) as u32 // transform the letters into bits: a = 1, b = 10, c = 100, etc.
-                })
+                .map(|c| (1 << (*c as u32 - 'a' as u32)) as u32) // transform the letters into bits: a = 1, b = 10, c = 100, etc.
                 .fold(0, |acc, bits| acc | bits) // calculate sum of bitwise OR: 'aabb' = 11, 'abcd' = 1111, 'abce' = 10111
         })
         .position(|sum_of_bitwise_or: u32| sum_of_bitwise_or.count_ones() == 4) // a sequence of four different letters will be indicated by four 1's
-        .expect("Didn't find a consecutive sequence of four different letters!");
-    Some(first_marker_index + 4)
+        .ok_or_else(|| {
+            PuzzleError::Unsolvable("there are no four different letters in a row".to_string())
+        })?;
+    Ok(first_marker_index + 4)
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
+pub fn part_two(input: &Input) -> Result<usize, PuzzleError> {
     // Same idea as above, except now we're looking for a sequence of 14 distinct characters
-    let first_marker_index = input
-        .as_bytes()
+    let first_marker_index = parse_datastream(input)?
         .windows(14)
         .map(|window| {
             window
                 .iter()
-                .map(|c| {
-                    assert!(c.is_ascii_lowercase());
-                    (1 << (*c as u32 - 'a' as u32)) as u32
-                })
+                .map(|c| (1 << (*c as u32 - 'a' as u32)) as u32)
                 .fold(0, |acc, bits| acc | bits)
         })
         .position(|sum_of_bitwise_or: u32| sum_of_bitwise_or.count_ones() == 14)
-        .expect("Didn't find a consecutive sequence of 14 different letters!");
-    Some(first_marker_index + 14)
+        .ok_or_else(|| {
+            PuzzleError::Unsolvable("there are no 14 different letters in a row".to_string())
+        })?;
+    Ok(first_marker_index + 14)
 }
 
 fn main() {
@@ -47,16 +56,54 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
+    use std::collections::HashSet;
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 6);
-        assert_eq!(part_one(&input), Some(10));
+        assert_eq!(part_one(&input), Ok(10));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 6);
-        assert_eq!(part_two(&input), Some(29));
+        assert_eq!(part_two(&input), Ok(29));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(6, |input| part_one(input).and(part_two(input)));
+    }
+
+    /// Random datastream, with a run of 14 different letters somewhere so that both markers exist
+    fn random_datastream(rng: &mut Rng) -> String {
+        let num_letters = rng.range(1..27);
+        let mut datastream: Vec<u8> = (0..rng.range(0..60))
+            .map(|_| b'a' + rng.range(0..num_letters) as u8)
+            .collect();
+        let mut marker: Vec<u8> = (b'a'..=b'z').collect();
+        rng.shuffle(&mut marker);
+        let position = rng.range(0..datastream.len() + 1);
+        datastream.splice(position..position, marker[..14].iter().copied());
+        String::from_utf8(datastream).unwrap()
+    }
+
+    /// Position after the first window of `size` different letters, by putting them in a set
+    fn reference_marker(datastream: &str, size: usize) -> Option<usize> {
+        let position = datastream
+            .as_bytes()
+            .windows(size)
+            .position(|window| window.iter().collect::<HashSet<_>>().len() == size)?;
+        Some(position + size)
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_datastream, |datastream| {
+            let input = Input::new(datastream);
+            assert_eq!(part_one(&input).ok(), reference_marker(datastream, 4));
+            assert_eq!(part_two(&input).ok(), reference_marker(datastream, 14));
+        });
     }
 }
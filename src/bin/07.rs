@@ -1,8 +1,9 @@
+use advent_of_code::{error::ParseError, Input};
 use std::collections::HashMap;
 
-pub fn part_one(input: &str) -> Option<u32> {
+pub fn part_one(input: &Input) -> Result<u32, ParseError> {
     // create the file and directory structure
-    let root_dir = create_files_and_directories(input);
+    let root_dir = create_files_and_directories(input)?;
 
     // create a flattened list of directories with their names and sizes
     let flattened_dirs = root_dir.get_all_dirs();
@@ -12,26 +13,33 @@ pub fn part_one(input: &str) -> Option<u32> {
         .filter(|(_, _, size)| size <= &100000)
         .fold(0, |acc, (_, _, size)| acc + size);
 
-    Some(sum)
+    Ok(sum)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &Input) -> Result<u32, ParseError> {
     // create the file and directory structure
-    let root_dir = create_files_and_directories(input);
+    let root_dir = create_files_and_directories(input)?;
 
     // figure out current size and how much space we need
     let current_size = root_dir.calc_size();
-    let needed_space = 30000000 - (70000000 - current_size);
+    let free_space = 70000000_u32.checked_sub(current_size).ok_or_else(|| {
+        ParseError::at(
+            input,
+            input,
+            "files that fit on the disk (70000000 in total)",
+        )
+    })?;
+    let needed_space = 30000000_u32.saturating_sub(free_space);
 
     // sort the directories by increasing size, and find the first one >= needed_space
     let mut flattened_dirs: Vec<(&Directory, &str, u32)> = root_dir.get_all_dirs().collect();
-    flattened_dirs.sort_by(|(_, _, a_size), (_, _, b_size)| a_size.cmp(b_size));
+    flattened_dirs.sort_by_key(|(_, _, a_size)| *a_size);
     let dir_to_delete = flattened_dirs
         .iter()
         .find(|(_, _, size)| *size >= needed_space)
         .expect("No directory found to delete!");
 
-    Some(dir_to_delete.2)
+    Ok(dir_to_delete.2)
 }
 
 fn main() {
@@ -43,17 +51,97 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_one(&input), Some(95437));
+        assert_eq!(part_one(&input), Ok(95437));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_two(&input), Some(24933642));
+        assert_eq!(part_two(&input), Ok(24933642));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(7, |input| part_one(input).and(part_two(input)));
+    }
+
+    #[derive(Debug)]
+    struct RandomDir {
+        file_sizes: Vec<u32>,
+        subdirs: Vec<RandomDir>,
+    }
+
+    fn random_dir(rng: &mut Rng, depth: usize) -> RandomDir {
+        let file_sizes = (0..rng.range(0..4))
+            .map(|_| rng.range(1..60000) as u32)
+            .collect();
+        let num_subdirs = if depth < 3 { rng.range(0..4) } else { 0 };
+        RandomDir {
+            file_sizes,
+            subdirs: (0..num_subdirs)
+                .map(|_| random_dir(rng, depth + 1))
+                .collect(),
+        }
+    }
+
+    /// Random file system, with a large file somewhere so that space needs to be freed up
+    fn random_file_system(rng: &mut Rng) -> RandomDir {
+        let mut root = random_dir(rng, 0);
+        let mut dir = &mut root;
+        while !dir.subdirs.is_empty() && rng.chance(2, 3) {
+            let subdir = rng.range(0..dir.subdirs.len());
+            dir = &mut dir.subdirs[subdir];
+        }
+        dir.file_sizes
+            .push(rng.range(40_000_000..45_000_000) as u32);
+        root
+    }
+
+    /// Terminal output of browsing the file system depth-first, and going back up with 'cd ..'
+    fn terminal_output(dir: &RandomDir, output: &mut String) {
+        output.push_str("$ ls\n");
+        for (i, size) in dir.file_sizes.iter().enumerate() {
+            output.push_str(&format!("{size} file{i}.txt\n"));
+        }
+        for i in 0..dir.subdirs.len() {
+            output.push_str(&format!("dir d{i}\n"));
+        }
+        for (i, subdir) in dir.subdirs.iter().enumerate() {
+            output.push_str(&format!("$ cd d{i}\n"));
+            terminal_output(subdir, output);
+            output.push_str("$ cd ..\n");
+        }
+    }
+
+    /// Total size of every directory, summed up from the generated structure
+    fn reference_sizes(dir: &RandomDir, sizes: &mut Vec<u32>) -> u32 {
+        let files: u32 = dir.file_sizes.iter().sum();
+        let subdirs: u32 = dir.subdirs.iter().map(|d| reference_sizes(d, sizes)).sum();
+        sizes.push(files + subdirs);
+        files + subdirs
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_file_system, |root| {
+            let mut output = String::from("$ cd /\n");
+            terminal_output(root, &mut output);
+            let input = Input::new(&output);
+
+            let mut sizes = vec![];
+            let total_size = reference_sizes(root, &mut sizes);
+            let small_dirs = sizes.iter().filter(|size| **size <= 100000);
+            assert_eq!(part_one(&input), Ok(small_dirs.sum()));
+
+            let needed_space = total_size - 40_000_000;
+            let smallest_deletion = sizes.iter().filter(|size| **size >= needed_space).min();
+            assert_eq!(part_two(&input).ok(), smallest_deletion.copied());
+        });
     }
 }
 
@@ -71,10 +159,10 @@ impl Directory {
     }
     fn calc_size(&self) -> u32 {
         let mut size = 0_u32;
-        for (_, file) in &self.files {
+        for file in self.files.values() {
             size += file.size;
         }
-        for (_, directory) in &self.directories {
+        for directory in self.directories.values() {
             size += directory.calc_size();
         }
         size
@@ -121,7 +209,7 @@ enum FileOrDir {
 fn parse_command(raw_str: &str) -> Option<Command> {
     match raw_str.get(0..4) {
         Some("$ cd") => {
-            let dir_name = raw_str.strip_prefix("$ cd ").unwrap();
+            let dir_name = raw_str.strip_prefix("$ cd ")?;
             if dir_name == "/" {
                 Some(Command::RootDir)
             } else if dir_name == ".." {
@@ -139,10 +227,10 @@ fn parse_command(raw_str: &str) -> Option<Command> {
 
 /** Parse file or directory name: 'dir foo' or '300 foo.txt' */
 fn parse_file_or_dir(raw_str: &str) -> Option<FileOrDir> {
-    if let Some(_) = raw_str.strip_prefix("dir ") {
+    if raw_str.strip_prefix("dir ").is_some() {
         return Some(FileOrDir::Dir);
     } else if let Some((raw_size, file_name)) = raw_str.split_once(' ') {
-        let size: u32 = raw_size.parse().expect("Couldn't parse file size!");
+        let size: u32 = raw_size.parse().ok()?;
         return Some(FileOrDir::File {
             file: File::new(size),
             name: file_name.to_owned(),
@@ -152,10 +240,12 @@ fn parse_file_or_dir(raw_str: &str) -> Option<FileOrDir> {
 }
 
 /** Create the file and directory structure from the input file, and return the root folder */
-fn create_files_and_directories(input: &str) -> Directory {
+fn create_files_and_directories(input: &str) -> Result<Directory, ParseError> {
     // Stacks to keep track of where we are in the directory structure
     let mut stack_dirs: Vec<Directory> = vec![Directory::new()];
     let mut stack_names: Vec<String> = vec![String::from("/")];
+    // sizes are added up as u32, so make sure that even the sum of all files fits
+    let mut total_size = 0_u32;
 
     for raw_str in input.lines() {
         // check if this line is a command
@@ -167,6 +257,13 @@ fn create_files_and_directories(input: &str) -> Directory {
                 stack_names = vec![String::from("/")];
             }
             Some(Command::UpDir) => {
+                if stack_dirs.len() == 1 {
+                    return Err(ParseError::at(
+                        input,
+                        raw_str,
+                        "a directory to leave (not /)",
+                    ));
+                }
                 let current_dir = stack_dirs.pop().expect("Current directory not found!");
                 let current_dir_name = stack_names.pop().unwrap();
                 stack_dirs
@@ -185,11 +282,20 @@ fn create_files_and_directories(input: &str) -> Directory {
             // if line is not a command, parse and insert the files and directories in the current directory
             _ => match parse_file_or_dir(raw_str) {
                 Some(FileOrDir::File { file, name }) => {
+                    total_size = total_size.checked_add(file.size).ok_or_else(|| {
+                        ParseError::at(input, raw_str, "files that add up to less than 2^32")
+                    })?;
                     let current_dir = stack_dirs.last_mut().expect("Current directory not found!");
                     current_dir.files.insert(name, file);
                 }
                 Some(FileOrDir::Dir) => (), // ignore directories as we're adding them above
-                None => panic!("Couldn't detect command or file/directory!"),
+                None => {
+                    return Err(ParseError::at(
+                        input,
+                        raw_str,
+                        "a command or a file/directory",
+                    ))
+                }
             },
         }
     }
@@ -204,5 +310,5 @@ fn create_files_and_directories(input: &str) -> Directory {
             .entry(current_dir_name)
             .or_insert(current_dir); // Would need to add merging here!
     }
-    stack_dirs.pop().unwrap()
+    Ok(stack_dirs.pop().unwrap())
 }
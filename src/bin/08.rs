@@ -1,4 +1,8 @@
-use advent_of_code::grid::{Grid, GridCoord};
+use advent_of_code::{
+    error::ParseError,
+    grid::{Grid, GridCoord},
+    Input,
+};
 
 #[derive(Clone, Default, Debug)]
 struct Tree {
@@ -6,41 +10,56 @@ struct Tree {
 }
 
 enum Direction {
-    UP,
-    DOWN,
-    LEFT,
-    RIGHT,
+    Up,
+    Down,
+    Left,
+    Right,
 }
 impl Direction {
     fn get_dx_dy(&self) -> (isize, isize) {
         match self {
-            Direction::UP => (0, -1),
-            Direction::DOWN => (0, 1),
-            Direction::RIGHT => (1, 0),
-            Direction::LEFT => (-1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Right => (1, 0),
+            Direction::Left => (-1, 0),
         }
     }
 }
 
-fn build_tree_grid_from_input(input: &str) -> Grid<Tree> {
+fn build_tree_grid_from_input(input: &str) -> Result<Grid<Tree>, ParseError> {
     let mut input_lines = input.lines().peekable();
-    let mut tree_grid: Grid<Tree> = Grid::new(
-        input_lines.peek().unwrap().len(),
-        input_lines.clone().count(),
-    );
+    let first_line = input_lines
+        .peek()
+        .ok_or_else(|| ParseError::at(input, input, "a row of trees"))?;
+    let mut tree_grid: Grid<Tree> =
+        Grid::new(first_line.chars().count(), input_lines.clone().count());
     for (y, input_line) in input_lines.enumerate() {
-        for (x, raw_height) in input_line.chars().enumerate() {
-            let tree = tree_grid
-                .get_cell_mut(&GridCoord { x, y })
-                .expect("Cell not found!");
-            tree.height =
-                i8::from_str_radix(&raw_height.to_string(), 10).expect("Couldn't parse height!");
+        if input_line.chars().count() < tree_grid.width {
+            return Err(ParseError::at(
+                input,
+                &input_line[input_line.len()..],
+                "more trees (rows must have the same length)",
+            ));
+        }
+        for (x, (byte_pos, raw_height)) in input_line.char_indices().enumerate() {
+            let position = &input_line[byte_pos..];
+            let tree = tree_grid.get_cell_mut(&GridCoord { x, y }).ok_or_else(|| {
+                ParseError::at(
+                    input,
+                    position,
+                    "end of row (rows must have the same length)",
+                )
+            })?;
+            tree.height = raw_height
+                .to_digit(10)
+                .and_then(|height| i8::try_from(height).ok())
+                .ok_or_else(|| ParseError::at(input, position, "a tree height (0-9)"))?;
         }
     }
-    tree_grid
+    Ok(tree_grid)
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
+pub fn part_one(input: &Input) -> Result<usize, ParseError> {
     /// Helper function to check if a tree is visible in a certain direction
     fn is_tree_visible_in_direction(
         grid: &Grid<Tree>,
@@ -53,7 +72,7 @@ pub fn part_one(input: &str) -> Option<usize> {
                 x: coord.x.checked_add_signed(dx * i)?,
                 y: coord.y.checked_add_signed(dy * i)?,
             };
-            Some(grid.get_cell(&coord)?)
+            grid.get_cell(&coord)
         });
         let mut is_visible = true;
         let this_height = grid
@@ -70,14 +89,14 @@ pub fn part_one(input: &str) -> Option<usize> {
     }
 
     // Build tree grid
-    let tree_grid: Grid<Tree> = build_tree_grid_from_input(input);
+    let tree_grid: Grid<Tree> = build_tree_grid_from_input(input)?;
 
     // Find out how many trees are visible
     let all_directions = [
-        Direction::DOWN,
-        Direction::UP,
-        Direction::RIGHT,
-        Direction::LEFT,
+        Direction::Down,
+        Direction::Up,
+        Direction::Right,
+        Direction::Left,
     ];
     let num_visible_trees = tree_grid
         .iter_all_cells()
@@ -88,10 +107,10 @@ pub fn part_one(input: &str) -> Option<usize> {
         })
         .count();
 
-    Some(num_visible_trees)
+    Ok(num_visible_trees)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &Input) -> Result<u32, ParseError> {
     fn calc_visible_trees_in_direction(
         grid: &Grid<Tree>,
         coord: &GridCoord,
@@ -103,7 +122,7 @@ pub fn part_two(input: &str) -> Option<u32> {
                 x: coord.x.checked_add_signed(dx * i)?,
                 y: coord.y.checked_add_signed(dy * i)?,
             };
-            Some(grid.get_cell(&coord)?)
+            grid.get_cell(&coord)
         });
         let mut num_visible_trees: u32 = 0;
         let this_height = grid
@@ -120,22 +139,22 @@ pub fn part_two(input: &str) -> Option<u32> {
     }
 
     // Build the tree grid
-    let tree_grid: Grid<Tree> = build_tree_grid_from_input(input);
+    let tree_grid: Grid<Tree> = build_tree_grid_from_input(input)?;
 
     // For each tree, calculate # of visible trees in all directions and calculate the scenic score
     let max_scenic_score = tree_grid
         .iter_all_cells()
         .map(|(coord, _)| {
-            let top = calc_visible_trees_in_direction(&tree_grid, coord, Direction::UP);
-            let bottom = calc_visible_trees_in_direction(&tree_grid, coord, Direction::DOWN);
-            let left = calc_visible_trees_in_direction(&tree_grid, coord, Direction::LEFT);
-            let right = calc_visible_trees_in_direction(&tree_grid, coord, Direction::RIGHT);
+            let top = calc_visible_trees_in_direction(&tree_grid, &coord, Direction::Up);
+            let bottom = calc_visible_trees_in_direction(&tree_grid, &coord, Direction::Down);
+            let left = calc_visible_trees_in_direction(&tree_grid, &coord, Direction::Left);
+            let right = calc_visible_trees_in_direction(&tree_grid, &coord, Direction::Right);
 
             top * bottom * left * right
         })
         .max();
 
-    max_scenic_score
+    Ok(max_scenic_score.unwrap_or_default())
 }
 
 fn main() {
@@ -147,16 +166,90 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 8);
-        assert_eq!(part_one(&input), Some(21));
+        assert_eq!(part_one(&input), Ok(21));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 8);
-        assert_eq!(part_two(&input), Some(8));
+        assert_eq!(part_two(&input), Ok(8));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = part_one(&Input::new("303\n2x5\n653")).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+        assert_eq!(error.expected, "a tree height (0-9)");
+
+        let error = part_two(&Input::new("303\n2551\n653")).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+
+        let error = part_one(&Input::new("303\n25\n653")).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(
+            error.expected,
+            "more trees (rows must have the same length)"
+        );
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(8, |input| part_one(input).and(part_two(input)));
+    }
+
+    fn random_heights(rng: &mut Rng) -> Vec<Vec<u32>> {
+        let (width, height) = (rng.range(1..8), rng.range(1..8));
+        (0..height)
+            .map(|_| (0..width).map(|_| rng.range(0..10) as u32).collect())
+            .collect()
+    }
+
+    /// The trees seen from (x, y) looking in each direction, nearest first
+    fn reference_lines_of_sight(heights: &[Vec<u32>], x: usize, y: usize) -> [Vec<u32>; 4] {
+        let row = &heights[y];
+        let col: Vec<u32> = heights.iter().map(|row| row[x]).collect();
+        [
+            row[..x].iter().rev().copied().collect(),
+            row[x + 1..].to_vec(),
+            col[..y].iter().rev().copied().collect(),
+            col[y + 1..].to_vec(),
+        ]
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_heights, |heights| {
+            let rows: Vec<String> = heights
+                .iter()
+                .map(|row| row.iter().map(|h| h.to_string()).collect())
+                .collect();
+            let input = Input::new(&rows.join("\n"));
+
+            let mut num_visible = 0;
+            let mut max_scenic_score = 0;
+            for (y, row) in heights.iter().enumerate() {
+                for (x, &tree) in row.iter().enumerate() {
+                    let lines = reference_lines_of_sight(heights, x, y);
+                    if lines.iter().any(|line| line.iter().all(|h| *h < tree)) {
+                        num_visible += 1;
+                    }
+                    let scenic_score: u32 = lines
+                        .iter()
+                        .map(|line| match line.iter().position(|h| *h >= tree) {
+                            Some(blocking_tree) => blocking_tree as u32 + 1,
+                            None => line.len() as u32,
+                        })
+                        .product();
+                    max_scenic_score = max_scenic_score.max(scenic_score);
+                }
+            }
+            assert_eq!(part_one(&input), Ok(num_visible));
+            assert_eq!(part_two(&input), Ok(max_scenic_score));
+        });
     }
 }
@@ -1,10 +1,20 @@
+use advent_of_code::{
+    error::ParseError,
+    helpers::{lines_of, parse_all, parse_num},
+    Input,
+};
+use nom::{
+    branch::alt, character::complete::char, combinator::value, error::context,
+    sequence::separated_pair,
+};
 use std::collections::HashSet;
 
+#[derive(Clone)]
 enum Direction {
-    UP,
-    DOWN,
-    LEFT,
-    RIGHT,
+    Up,
+    Down,
+    Left,
+    Right,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -15,30 +25,29 @@ struct Coord {
 impl Coord {
     fn step(&mut self, direction: &Direction) {
         match direction {
-            Direction::UP => self.y += 1,
-            Direction::DOWN => self.y -= 1,
-            Direction::LEFT => self.x -= 1,
-            Direction::RIGHT => self.x += 1,
+            Direction::Up => self.y += 1,
+            Direction::Down => self.y -= 1,
+            Direction::Left => self.x -= 1,
+            Direction::Right => self.x += 1,
         }
     }
 }
 
-fn parse_instructions(input: &str) -> Vec<(Direction, u8)> {
-    let mut instructions: Vec<(Direction, u8)> = vec![];
-    for line in input.lines() {
-        let (raw_direction, raw_steps) = line
-            .split_once(' ')
-            .expect("Should be a space in each instruction");
-        let direction = match raw_direction {
-            "U" => Direction::UP,
-            "R" => Direction::RIGHT,
-            "L" => Direction::LEFT,
-            "D" => Direction::DOWN,
-            _ => panic!("Unrecognized direction!"),
-        };
-        instructions.push((direction, raw_steps.parse().expect("Should be integer")));
-    }
-    instructions
+/// Parse the motions of the head, like `R 4`
+fn parse_instructions(input: &str) -> Result<Vec<(Direction, u8)>, ParseError> {
+    let direction = context(
+        "a direction (U, D, L or R)",
+        alt((
+            value(Direction::Up, char('U')),
+            value(Direction::Right, char('R')),
+            value(Direction::Left, char('L')),
+            value(Direction::Down, char('D')),
+        )),
+    );
+    parse_all(
+        input,
+        lines_of(separated_pair(direction, char(' '), parse_num::<u8>)),
+    )
 }
 
 /// Helper function to move the rope tail so that it follows the head
@@ -69,8 +78,8 @@ fn move_tail(tail: &mut Coord, head: &Coord) {
     }
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let instructions = parse_instructions(input);
+pub fn part_one(input: &Input) -> Result<usize, ParseError> {
+    let instructions = parse_instructions(input)?;
     let mut visited_coords: HashSet<Coord> = HashSet::new();
 
     let mut head = Coord { x: 0, y: 0 };
@@ -80,19 +89,19 @@ pub fn part_one(input: &str) -> Option<usize> {
         for _ in 0..steps {
             head.step(&dir);
             move_tail(&mut tail, &head);
-            visited_coords.insert(tail.clone());
+            visited_coords.insert(tail);
         }
     }
 
-    Some(visited_coords.len())
+    Ok(visited_coords.len())
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let instructions = parse_instructions(input);
+pub fn part_two(input: &Input) -> Result<usize, ParseError> {
+    let instructions = parse_instructions(input)?;
     let mut visited_coords: HashSet<Coord> = HashSet::new();
 
     let mut head = Coord { x: 0, y: 0 };
-    let mut tails = [head.clone(); 9];
+    let mut tails = [head; 9];
 
     for (dir, steps) in instructions {
         for _ in 0..steps {
@@ -102,11 +111,11 @@ pub fn part_two(input: &str) -> Option<usize> {
                 move_tail(tail, prev_tail);
                 prev_tail = tail;
             }
-            visited_coords.insert(tails.last().unwrap().clone());
+            visited_coords.insert(*tails.last().unwrap());
         }
     }
 
-    Some(visited_coords.len())
+    Ok(visited_coords.len())
 }
 
 fn main() {
@@ -118,16 +127,68 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 9);
-        assert_eq!(part_one(&input), Some(13));
+        assert_eq!(part_one(&input), Ok(13));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 9);
-        assert_eq!(part_two(&input), Some(1));
+        assert_eq!(part_two(&input), Ok(1));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(9, |input| part_one(input).and(part_two(input)));
+    }
+
+    fn random_moves(rng: &mut Rng) -> Vec<(char, u8)> {
+        (0..rng.range(1..30))
+            .map(|_| (*rng.choose(&['U', 'D', 'L', 'R']), rng.range(1..10) as u8))
+            .collect()
+    }
+
+    /// Simulate the rope one step at a time: a knot that isn't touching the previous one anymore
+    /// moves one step towards it, on both axes
+    fn reference_tail_positions(moves: &[(char, u8)], num_knots: usize) -> usize {
+        let mut knots = vec![(0_i32, 0_i32); num_knots];
+        let mut visited = HashSet::from([(0, 0)]);
+        for &(direction, steps) in moves {
+            for _ in 0..steps {
+                match direction {
+                    'U' => knots[0].1 += 1,
+                    'D' => knots[0].1 -= 1,
+                    'L' => knots[0].0 -= 1,
+                    _ => knots[0].0 += 1,
+                }
+                for i in 1..num_knots {
+                    let (dx, dy) = (knots[i - 1].0 - knots[i].0, knots[i - 1].1 - knots[i].1);
+                    if dx.abs() > 1 || dy.abs() > 1 {
+                        knots[i].0 += dx.signum();
+                        knots[i].1 += dy.signum();
+                    }
+                }
+                visited.insert(knots[num_knots - 1]);
+            }
+        }
+        visited.len()
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_moves, |moves| {
+            let input = Input::new(
+                &moves
+                    .iter()
+                    .map(|(direction, steps)| format!("{direction} {steps}\n"))
+                    .collect::<String>(),
+            );
+            assert_eq!(part_one(&input), Ok(reference_tail_positions(moves, 2)));
+            assert_eq!(part_two(&input), Ok(reference_tail_positions(moves, 10)));
+        });
     }
 }
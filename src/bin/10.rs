@@ -1,150 +1,620 @@
-use std::collections::VecDeque;
+use advent_of_code::{
+    error::{ParseError, PuzzleError},
+    grid::Grid,
+    helpers::{lines_of, parse_all, parse_num, ParseResult},
+    ocr::Screen,
+    Input,
+};
+use nom::{
+    bytes::complete::tag,
+    character::complete::char,
+    error::{context, ErrorKind, ParseError as _, VerboseError},
+    sequence::preceded,
+};
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    ops::{ControlFlow, Index, IndexMut},
+    process,
+};
 
-enum Instruction {
-    NoOp,
-    AddX(i32),
+/// An instruction of the instruction set
+struct Operation {
+    name: &'static str,
+    /** whether the instruction takes a value, like `addx 3` */
+    takes_value: bool,
+    /** number of cycles the instruction takes to complete */
+    cycles: u32,
+    /** changes the registers once the instruction completes, `None` on overflow */
+    execute: fn(&mut Registers, i32) -> Option<()>,
+}
+
+/// The registers and instructions of a CPU
+struct InstructionSet {
+    /** the name of every register, and its value when a program starts */
+    registers: &'static [(&'static str, i32)],
+    operations: &'static [Operation],
+    /** the instructions as they're written, for parse errors */
+    syntax: &'static str,
+}
+
+/// The register with a name among `registers` (see `InstructionSet::registers`), ignoring case
+fn find_register(registers: &'static [(&'static str, i32)], name: &str) -> Option<Register> {
+    let index = registers
+        .iter()
+        .position(|(register, _)| register.eq_ignore_ascii_case(name))?;
+    Some(Register {
+        index,
+        name: registers[index].0,
+    })
+}
+
+impl InstructionSet {
+    /// The register with a name, ignoring case
+    fn register(&self, name: &str) -> Option<Register> {
+        find_register(self.registers, name)
+    }
+
+    /// Parse a program of instructions of the set, one per line
+    fn parse_program(&'static self, input: &str) -> Result<Program, ParseError> {
+        let instructions = parse_all(
+            input,
+            lines_of(context(self.syntax, |input| {
+                parse_instruction(self.operations, input)
+            })),
+        )?;
+        Ok(Program {
+            instruction_set: self,
+            instructions,
+        })
+    }
+}
+
+const INSTRUCTION_SET: InstructionSet = InstructionSet {
+    registers: &[("X", 1)],
+    operations: &[
+        Operation {
+            name: "noop",
+            takes_value: false,
+            cycles: 1,
+            execute: |_, _| Some(()),
+        },
+        Operation {
+            name: "addx",
+            takes_value: true,
+            cycles: 2,
+            execute: |registers, value| {
+                registers[X] = registers[X].checked_add(value)?;
+                Some(())
+            },
+        },
+    ],
+    syntax: "noop or addx <value>",
+};
+
+/// The X register of `INSTRUCTION_SET`. The signal and the sprite of the CRT are the register of
+/// that name, looked up in the instruction set of the program.
+const X: Register = Register {
+    index: 0,
+    name: "X",
+};
+
+#[derive(Clone, Copy)]
+struct Instruction {
+    operation: &'static Operation,
+    /** the value of the instruction, 0 for instructions without one */
+    value: i32,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.operation.takes_value {
+            write!(f, "{} {}", self.operation.name, self.value)
+        } else {
+            write!(f, "{}", self.operation.name)
+        }
+    }
+}
+
+/// A parsed program, with the instruction set it runs on
+#[derive(Clone)]
+struct Program {
+    instruction_set: &'static InstructionSet,
+    instructions: Vec<Instruction>,
+}
+
+/// A register of an instruction set
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Register {
+    /** index of the register in `InstructionSet::registers` */
+    index: usize,
+    name: &'static str,
+}
+
+/// Most registers an instruction set can have
+const MAX_REGISTERS: usize = 4;
+
+#[derive(Clone, Copy)]
+struct Registers {
+    /** the registers of the instruction set, see `InstructionSet::registers` */
+    names: &'static [(&'static str, i32)],
+    values: [i32; MAX_REGISTERS],
+}
+
+impl Registers {
+    /// The registers of an instruction set, with their initial values
+    fn new(instruction_set: &InstructionSet) -> Self {
+        let names = instruction_set.registers;
+        assert!(names.len() <= MAX_REGISTERS, "too many registers");
+        let mut values = [0; MAX_REGISTERS];
+        for (value, (_, initial)) in values.iter_mut().zip(names) {
+            *value = *initial;
+        }
+        Self { names, values }
+    }
+
+    /// The register with a name, ignoring case, or an error if the instruction set doesn't have it
+    fn find(&self, name: &'static str) -> Result<Register, CpuError> {
+        find_register(self.names, name).ok_or(CpuError::MissingRegister(name))
+    }
+}
+
+impl Index<Register> for Registers {
+    type Output = i32;
+
+    fn index(&self, register: Register) -> &i32 {
+        &self.values[register.index]
+    }
+}
+
+impl IndexMut<Register> for Registers {
+    fn index_mut(&mut self, register: Register) -> &mut i32 {
+        &mut self.values[register.index]
+    }
+}
+
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, ((name, _), value)) in self.names.iter().zip(self.values).enumerate() {
+            let separator = if i == 0 { "" } else { " " };
+            write!(f, "{separator}{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of the CPU during a cycle
+struct Tick {
+    /** number of the cycle, starting at 1 */
+    cycle: u32,
+    instruction: Instruction,
+    /** the registers during the cycle, before the instruction completes */
+    registers: Registers,
 }
 
 struct Cpu {
+    program: Vec<Instruction>,
+    /** index of the current instruction in the program */
+    pointer: usize,
+    /** number of cycles the current instruction has run for */
+    instruction_cycle: u32,
     /** the overall cycle count */
     cycle: u32,
-    /** the current value in the register */
-    register_value: i32,
-    /** the currently loaded instruction */
-    current_instruction: Instruction,
-    /** cycle count for the current instruction */
-    current_cycle: u8,
-    /** a queue of CPU instructions */
-    instructions: VecDeque<Instruction>,
+    registers: Registers,
 }
 
 impl Cpu {
-    fn new(mut instructions: VecDeque<Instruction>) -> Self {
-        let current_instruction = instructions
-            .pop_front()
-            .expect("Should have at least 1 instruction!");
+    fn new(program: Program) -> Self {
         Self {
+            registers: Registers::new(program.instruction_set),
+            program: program.instructions,
+            pointer: 0,
+            instruction_cycle: 0,
             cycle: 0,
-            register_value: 1,
-            current_instruction,
-            current_cycle: 0,
-            instructions,
         }
     }
-    fn load_next_instruction(&mut self) {
-        self.current_cycle = 0;
-        self.current_instruction = self
-            .instructions
-            .pop_front()
-            .expect("Should have next instruction!");
-    }
-    fn simulate_cycle(&mut self) {
-        match self.current_instruction {
-            Instruction::NoOp => {
-                if self.current_cycle == 1 {
-                    self.load_next_instruction();
-                }
-            }
-            Instruction::AddX(x) => {
-                if self.current_cycle == 2 {
-                    self.register_value += x;
-                    self.load_next_instruction();
-                }
+
+    /// Run the next cycle, or return `None` once the program has halted. An instruction only
+    /// changes the registers when the cycle after it starts, so a cycle sees the registers as
+    /// they are during that cycle.
+    fn step(&mut self) -> Result<Option<Tick>, CpuError> {
+        if let Some(instruction) = self.program.get(self.pointer) {
+            if self.instruction_cycle == instruction.operation.cycles {
+                (instruction.operation.execute)(&mut self.registers, instruction.value)
+                    .ok_or(CpuError::Overflow)?;
+                self.pointer += 1;
+                self.instruction_cycle = 0;
             }
         }
+        let Some(&instruction) = self.program.get(self.pointer) else {
+            return Ok(None);
+        };
         self.cycle += 1;
-        self.current_cycle += 1;
+        self.instruction_cycle += 1;
+        Ok(Some(Tick {
+            cycle: self.cycle,
+            instruction,
+            registers: self.registers,
+        }))
     }
-    fn simulate_cycles(&mut self, num_cycles: u32) {
-        for _ in 0..num_cycles {
-            self.simulate_cycle();
+
+    /// Run until the program halts or the observer breaks, calling the observer every cycle
+    fn run(&mut self, mut observe: impl FnMut(&Tick) -> ControlFlow<()>) -> Result<(), CpuError> {
+        while let Some(tick) = self.step()? {
+            if observe(&tick).is_break() {
+                break;
+            }
         }
+        Ok(())
     }
-    fn calc_signal_strength(&self) -> i32 {
-        self.register_value * self.cycle as i32
+
+    /// Run `num_cycles` cycles, failing if the program halts before that
+    fn run_cycles(
+        &mut self,
+        num_cycles: u32,
+        mut observe: impl FnMut(&Tick),
+    ) -> Result<(), CpuError> {
+        self.run(|tick| {
+            observe(tick);
+            if tick.cycle >= num_cycles {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+        if self.cycle < num_cycles {
+            return Err(CpuError::OutOfInstructions);
+        }
+        Ok(())
     }
 }
 
-fn parse_cpu_instructions(input: &str) -> VecDeque<Instruction> {
-    let mut instructions: VecDeque<Instruction> = VecDeque::new();
-    for line in input.lines() {
-        match line.get(0..4) {
-            Some("noop") => instructions.push_back(Instruction::NoOp),
-            Some("addx") => {
-                let (_, value) = line
-                    .split_once(' ')
-                    .expect("Should be space in this instruction");
-                instructions.push_back(Instruction::AddX(
-                    value.parse().expect("Addx value should be integer"),
-                ))
+/// Ways a program can fail that only show up while running it
+enum CpuError {
+    OutOfInstructions,
+    Overflow,
+    /** the instruction set lacks a register that is read, like X for the signal */
+    MissingRegister(&'static str),
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpuError::OutOfInstructions => write!(f, "the program ends too early"),
+            CpuError::Overflow => write!(f, "the signal doesn't fit in 32 bits"),
+            CpuError::MissingRegister(name) => {
+                write!(f, "the instruction set has no {name} register")
             }
-            _ => panic!("Unrecognized instruction!"),
         }
     }
-    instructions
 }
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let mut cpu = Cpu::new(parse_cpu_instructions(input));
-    cpu.simulate_cycles(20);
-    let mut signal_strength = cpu.calc_signal_strength();
-    for _ in 0..5 {
-        cpu.simulate_cycles(40);
-        signal_strength += cpu.calc_signal_strength();
+/// The puzzle has no answer for a program that fails, but no single instruction is to blame
+impl From<CpuError> for PuzzleError {
+    fn from(error: CpuError) -> Self {
+        PuzzleError::Unsolvable(error.to_string())
     }
-    Some(signal_strength)
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-struct Coord {
+/// Parse an instruction of an instruction set, like `noop` or `addx 3`
+fn parse_instruction<'a>(
+    operations: &'static [Operation],
+    input: &'a str,
+) -> ParseResult<'a, Instruction> {
+    for operation in operations {
+        let Ok((rest, _)) = tag::<_, _, VerboseError<&str>>(operation.name)(input) else {
+            continue;
+        };
+        let (rest, value) = if operation.takes_value {
+            preceded(char(' '), parse_num::<i32>)(rest)?
+        } else {
+            (rest, 0)
+        };
+        return Ok((rest, Instruction { operation, value }));
+    }
+    Err(nom::Err::Error(VerboseError::from_error_kind(
+        input,
+        ErrorKind::Tag,
+    )))
+}
+
+fn parse_program(input: &str) -> Result<Program, ParseError> {
+    INSTRUCTION_SET.parse_program(input)
+}
+
+/// The signal during a cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sample {
+    cycle: u32,
     x: i32,
-    y: i32,
+    /** the cycle number times the X register */
+    signal_strength: i64,
 }
 
-fn get_sprite_positions(register_value: i32, vertical_pos: i32) -> [Coord; 3] {
-    let coord = Coord {
-        x: register_value,
-        y: vertical_pos,
+/// Sample the signal during each of `cycles`, in any order, e.g. a list like `[20, 60]` or a
+/// sequence like `(20..=220).step_by(40)`. Cycles are numbered from 1, so cycle 0 is skipped, and
+/// the samples are in cycle order without duplicates. Fails if the program halts before the last
+/// cycle.
+fn sample_signal(
+    cpu: &mut Cpu,
+    cycles: impl IntoIterator<Item = u32>,
+) -> Result<Vec<Sample>, CpuError> {
+    let mut cycles: Vec<u32> = cycles.into_iter().filter(|&cycle| cycle > 0).collect();
+    cycles.sort_unstable();
+    cycles.dedup();
+    let Some(&last_cycle) = cycles.last() else {
+        return Ok(vec![]);
     };
-    [
-        Coord {
-            x: coord.x - 1,
-            y: coord.y,
-        },
-        coord,
-        Coord {
-            x: coord.x + 1,
-            y: coord.y,
+
+    let x_register = cpu.registers.find(X.name)?;
+    let mut samples = Vec::with_capacity(cycles.len());
+    let mut cycles = cycles.into_iter().peekable();
+    cpu.run_cycles(last_cycle, |tick| {
+        if cycles.next_if_eq(&tick.cycle).is_some() {
+            let x = tick.registers[x_register];
+            samples.push(Sample {
+                cycle: tick.cycle,
+                x,
+                signal_strength: i64::from(tick.cycle) * i64::from(x),
+            });
+        }
+    })?;
+    Ok(samples)
+}
+
+pub fn part_one(input: &Input) -> Result<i32, PuzzleError> {
+    let mut cpu = Cpu::new(parse_program(input)?);
+    sample_signal(&mut cpu, (20..=220).step_by(40))
+        .and_then(|samples| {
+            let signal_strength: i64 = samples.iter().map(|sample| sample.signal_strength).sum();
+            i32::try_from(signal_strength).map_err(|_| CpuError::Overflow)
+        })
+        .map_err(PuzzleError::from)
+}
+
+/// The pixel the 40x6 CRT draws during a cycle, as its position and whether it's lit. The
+/// screen is done after cycle 240.
+fn pixel(tick: &Tick, sprite: Register) -> Option<((usize, usize), bool)> {
+    let pixel = tick.cycle.checked_sub(1).filter(|&pixel| pixel < 240)?;
+    let (x, y) = (pixel % 40, pixel / 40);
+    // the sprite is 3 pixels wide, centered on the sprite register
+    let is_lit = tick.registers[sprite].abs_diff(x as i32) <= 1;
+    Some(((x as usize, y as usize), is_lit))
+}
+
+fn draw(crt: &mut Grid<bool>, tick: &Tick, sprite: Register) {
+    if let Some((position, is_lit)) = pixel(tick, sprite) {
+        crt.insert_cell(position.into(), is_lit)
+            .expect("the screen is 40x6");
+    }
+}
+
+pub fn part_two(input: &Input) -> Result<Screen, PuzzleError> {
+    let mut cpu = Cpu::new(parse_program(input)?);
+    let sprite = cpu.registers.find(X.name)?;
+    let mut crt: Grid<bool> = Grid::new(40, 6);
+    cpu.run_cycles(240, |tick| draw(&mut crt, tick, sprite))?;
+
+    Ok(Screen::new(&crt))
+}
+
+/// A line like `cycle   3  addx -1      X=1  draws # at 2,0` for the trace and the debugger
+fn trace_line(tick: &Tick) -> String {
+    let pixel = match tick.registers.find(X.name) {
+        Ok(sprite) => match pixel(tick, sprite) {
+            Some(((x, y), is_lit)) => {
+                format!("draws {} at {x},{y}", if is_lit { '#' } else { '.' })
+            }
+            None => "off screen".to_string(),
         },
-    ]
-}
-
-pub fn part_two(input: &str) -> Option<String> {
-    let mut cpu = Cpu::new(parse_cpu_instructions(input));
-    let mut output = String::new();
-
-    for vertical_pos in 0..6 {
-        for horizontal_pos in 0..40 {
-            cpu.simulate_cycle();
-            let current_position = Coord {
-                x: horizontal_pos,
-                y: vertical_pos,
-            };
-            let sprite_positions = get_sprite_positions(cpu.register_value, vertical_pos);
-            if sprite_positions.contains(&current_position) {
-                output += "#";
-            } else {
-                output += ".";
+        Err(e) => e.to_string(),
+    };
+    let instruction = tick.instruction.to_string();
+    format!(
+        "cycle {:>3}  {instruction:<10}  {}  {pixel}",
+        tick.cycle, tick.registers
+    )
+}
+
+#[derive(Debug, PartialEq)]
+enum Breakpoint {
+    Cycle(u32),
+    Register(Register, i32),
+}
+
+impl Breakpoint {
+    fn is_hit(&self, tick: &Tick) -> bool {
+        match *self {
+            Breakpoint::Cycle(cycle) => tick.cycle == cycle,
+            Breakpoint::Register(register, value) => tick.registers[register] == value,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "cycle {cycle}"),
+            Breakpoint::Register(register, value) => write!(f, "{}={value}", register.name),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Step(u32),
+    Continue,
+    Break(Breakpoint),
+    Delete,
+    Screen,
+    Help,
+    Quit,
+}
+
+const DEBUGGER_HELP: &str = "\
+step [n]          run one (or n) cycles
+continue          run until a breakpoint is hit or the program halts
+break <cycle>     stop during a cycle
+break <reg>=<n>   stop during cycles where a register has a value, like break x=3
+delete            remove all breakpoints
+screen            show what the CRT has drawn so far, and the letters on it
+quit";
+
+/// Parse a line of the debugger, `None` for an empty line
+fn parse_command(instruction_set: &InstructionSet, line: &str) -> Result<Option<Command>, String> {
+    let mut words = line.split_whitespace();
+    let command = match (words.next(), words.next()) {
+        (None, _) => return Ok(None),
+        (Some("s" | "step"), None) => Command::Step(1),
+        (Some("s" | "step"), Some(n)) => {
+            Command::Step(n.parse().map_err(|_| format!("invalid step count: {n}"))?)
+        }
+        (Some("c" | "continue"), None) => Command::Continue,
+        (Some("b" | "break"), Some(target)) => Command::Break(match target.split_once('=') {
+            Some((name, value)) => {
+                let register = instruction_set
+                    .register(name)
+                    .ok_or_else(|| format!("unknown register: {name}"))?;
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("invalid register value: {value}"))?;
+                Breakpoint::Register(register, value)
             }
+            None => Breakpoint::Cycle(
+                target
+                    .parse()
+                    .map_err(|_| format!("invalid cycle: {target}"))?,
+            ),
+        }),
+        (Some("d" | "delete"), None) => Command::Delete,
+        (Some("screen"), None) => Command::Screen,
+        (Some("h" | "help"), None) => Command::Help,
+        (Some("q" | "quit"), None) => Command::Quit,
+        _ => return Err(format!("unknown command: {line} (try help)")),
+    };
+    if words.next().is_some() {
+        return Err(format!("too many arguments: {line}"));
+    }
+    Ok(Some(command))
+}
+
+/// Steps through a program, drawing the CRT as it goes
+struct Debugger {
+    cpu: Cpu,
+    crt: Grid<bool>,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    fn new(program: Program) -> Self {
+        Self {
+            cpu: Cpu::new(program),
+            crt: Grid::new(40, 6),
+            breakpoints: vec![],
         }
-        output += "\n";
     }
 
-    Some(output)
+    /// Run a command and return what to show for it
+    fn execute(&mut self, command: Command) -> String {
+        let mut output = vec![];
+        let mut run = |num_cycles: Option<u32>| {
+            for i in 1.. {
+                let tick = match self.cpu.step() {
+                    Ok(Some(tick)) => tick,
+                    Ok(None) => {
+                        output.push(format!("halted after {} cycles", self.cpu.cycle));
+                        return;
+                    }
+                    Err(e) => {
+                        output.push(format!("error: {e}"));
+                        return;
+                    }
+                };
+                // without a sprite register there's nothing to draw, which the trace shows
+                if let Ok(sprite) = tick.registers.find(X.name) {
+                    draw(&mut self.crt, &tick, sprite);
+                }
+                match num_cycles {
+                    Some(num_cycles) => {
+                        output.push(trace_line(&tick));
+                        if i == num_cycles {
+                            return;
+                        }
+                    }
+                    None => {
+                        if let Some(breakpoint) = self.breakpoints.iter().find(|b| b.is_hit(&tick))
+                        {
+                            output.push(format!("breakpoint {breakpoint}"));
+                            output.push(trace_line(&tick));
+                            return;
+                        }
+                    }
+                }
+            }
+        };
+        match command {
+            Command::Step(num_cycles) => run(Some(num_cycles.max(1))),
+            Command::Continue => run(None),
+            Command::Break(breakpoint) => {
+                output.push(format!("added breakpoint {breakpoint}"));
+                self.breakpoints.push(breakpoint);
+            }
+            Command::Delete => {
+                self.breakpoints.clear();
+                output.push("removed all breakpoints".to_string());
+            }
+            Command::Screen => output.push(
+                format!("{:#}", Screen::new(&self.crt))
+                    .trim_end()
+                    .to_string(),
+            ),
+            Command::Help => output.push(DEBUGGER_HELP.to_string()),
+            Command::Quit => {}
+        }
+        output.join("\n")
+    }
+}
+
+/// Read debugger commands from stdin until `quit` or the end of input
+fn debug(program: Program) {
+    let instruction_set = program.instruction_set;
+    let mut debugger = Debugger::new(program);
+    println!("day 10 debugger, type help for the commands");
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("(cpu) ");
+        io::stdout().flush().unwrap();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        match parse_command(instruction_set, &line) {
+            Ok(None) => {}
+            Ok(Some(Command::Quit)) => break,
+            Ok(Some(command)) => println!("{}", debugger.execute(command)),
+            Err(e) => println!("{e}"),
+        }
+    }
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 10);
+    let mut args = pico_args::Arguments::from_env();
+    // `cargo solve 10 -- --trace` shows every cycle, `-- --debug` steps through the program
+    let (trace, debug_program) = (args.contains("--trace"), args.contains("--debug"));
+    if trace || debug_program {
+        let program = parse_program(input).unwrap_or_else(|e| {
+            eprintln!("error: {e}");
+            process::exit(1);
+        });
+        if debug_program {
+            debug(program);
+        } else if let Err(e) = Cpu::new(program).run(|tick| {
+            println!("{}", trace_line(tick));
+            ControlFlow::Continue(())
+        }) {
+            eprintln!("error: {e}");
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -152,28 +622,300 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 10);
-        assert_eq!(part_one(&input), Some(13140));
+        assert_eq!(part_one(&input), Ok(13140));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 10);
+        let screen = part_two(&input).unwrap();
+        // the example doesn't draw letters
+        assert_eq!(screen.letters, None);
         assert_eq!(
-            part_two(&input),
-            Some(
-                r#"##..##..##..##..##..##..##..##..##..##..
+            screen.bitmap,
+            r#"##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
 ####....####....####....####....####....
 #####.....#####.....#####.....#####.....
 ######......######......######......####
 #######.......#######.......#######.....
 "#
-                .to_string()
-            )
         );
     }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(10, |input| part_one(input).and(part_two(input)));
+    }
+
+    #[test]
+    fn test_program_too_short() {
+        let input = Input::new("noop\naddx 3\naddx -5");
+        assert_eq!(
+            part_one(&input),
+            Err(PuzzleError::Unsolvable(
+                "the program ends too early".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_observer() {
+        let mut cpu = Cpu::new(parse_program("noop\naddx 3\naddx -5").unwrap());
+        let mut values = vec![];
+        cpu.run(|tick| {
+            values.push((tick.cycle, tick.registers[X]));
+            ControlFlow::Continue(())
+        })
+        .unwrap_or_else(|_| panic!("the program should halt"));
+        assert_eq!(values, [(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(cpu.registers[X], -1);
+        assert!(cpu.step().is_ok_and(|tick| tick.is_none()));
+
+        let mut cpu = Cpu::new(parse_program("noop\naddx 3\naddx -5").unwrap());
+        cpu.run(|tick| {
+            if tick.cycle == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap_or_else(|_| panic!("the observer should stop the program"));
+        assert_eq!((cpu.cycle, cpu.registers[X]), (3, 1));
+    }
+
+    /// An instruction set with a second register, to check that nothing assumes there's only X
+    const TWO_REGISTERS: InstructionSet = InstructionSet {
+        registers: &[("A", 1), ("B", -2)],
+        operations: &[
+            Operation {
+                name: "addb",
+                takes_value: true,
+                cycles: 1,
+                execute: |registers, value| {
+                    registers.values[1] = registers.values[1].checked_add(value)?;
+                    Some(())
+                },
+            },
+            Operation {
+                name: "swap",
+                takes_value: false,
+                cycles: 3,
+                execute: |registers, _| {
+                    registers.values.swap(0, 1);
+                    Some(())
+                },
+            },
+        ],
+        syntax: "addb <value> or swap",
+    };
+
+    #[test]
+    fn test_instruction_set() {
+        let program = TWO_REGISTERS
+            .parse_program("addb 5\nswap\naddb -1")
+            .unwrap();
+        let mut debugger = Debugger::new(program);
+        let mut run =
+            |line: &str| debugger.execute(parse_command(&TWO_REGISTERS, line).unwrap().unwrap());
+        assert_eq!(run("b a=3"), "added breakpoint A=3");
+        assert_eq!(
+            run("c"),
+            "breakpoint A=3\ncycle   5  addb -1     A=3 B=1  the instruction set has no X register"
+        );
+        assert_eq!(run("c"), "halted after 5 cycles");
+        assert_eq!(debugger.cpu.registers.to_string(), "A=3 B=0");
+
+        assert_eq!(
+            parse_command(&INSTRUCTION_SET, "b b=1"),
+            Err("unknown register: b".to_string())
+        );
+        let error = TWO_REGISTERS.parse_program("addb 1\nnoop").err().unwrap();
+        assert_eq!(error.expected, "addb <value> or swap");
+
+        // the signal is the X register, which this instruction set doesn't have
+        let program = TWO_REGISTERS.parse_program("addb 1").unwrap();
+        assert!(matches!(
+            sample_signal(&mut Cpu::new(program), [1]),
+            Err(CpuError::MissingRegister("X"))
+        ));
+    }
+
+    #[test]
+    fn test_sample_signal() {
+        let input = advent_of_code::read_file("examples", 10);
+        let program = parse_program(&input).unwrap();
+        let samples = sample_signal(&mut Cpu::new(program.clone()), (20..=220).step_by(40))
+            .unwrap_or_else(|_| panic!("the example runs for 240 cycles"));
+        let summary: Vec<(u32, i32, i64)> = samples
+            .iter()
+            .map(|sample| (sample.cycle, sample.x, sample.signal_strength))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (20, 21, 420),
+                (60, 19, 1140),
+                (100, 18, 1800),
+                (140, 21, 2940),
+                (180, 16, 2880),
+                (220, 18, 3960),
+            ]
+        );
+
+        let samples = sample_signal(&mut Cpu::new(program.clone()), [220, 20, 240, 20])
+            .unwrap_or_else(|_| panic!("the example runs for 240 cycles"));
+        let cycles: Vec<u32> = samples.iter().map(|sample| sample.cycle).collect();
+        assert_eq!(cycles, [20, 220, 240]);
+        assert_eq!(
+            samples[2],
+            Sample {
+                cycle: 240,
+                x: 17,
+                signal_strength: 4080
+            }
+        );
+        assert!(sample_signal(&mut Cpu::new(program.clone()), []).is_ok_and(|s| s.is_empty()));
+        assert!(sample_signal(&mut Cpu::new(program.clone()), [0]).is_ok_and(|s| s.is_empty()));
+        let samples = sample_signal(&mut Cpu::new(program.clone()), [0, 20])
+            .unwrap_or_else(|_| panic!("the example runs for 240 cycles"));
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].cycle, 20);
+        assert!(matches!(
+            sample_signal(&mut Cpu::new(program), [241]),
+            Err(CpuError::OutOfInstructions)
+        ));
+    }
+
+    #[test]
+    fn test_trace() {
+        let input = advent_of_code::read_file("examples", 10);
+        let mut lines = vec![];
+        Cpu::new(parse_program(&input).unwrap())
+            .run_cycles(4, |tick| lines.push(trace_line(tick)))
+            .unwrap_or_else(|_| panic!("the example has more than 4 cycles"));
+        assert_eq!(
+            lines,
+            [
+                "cycle   1  addx 15     X=1  draws # at 0,0",
+                "cycle   2  addx 15     X=1  draws # at 1,0",
+                "cycle   3  addx -11    X=16  draws . at 2,0",
+                "cycle   4  addx -11    X=16  draws . at 3,0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_debugger() {
+        let input = advent_of_code::read_file("examples", 10);
+        let mut debugger = Debugger::new(parse_program(&input).unwrap());
+        let mut run =
+            |line: &str| debugger.execute(parse_command(&INSTRUCTION_SET, line).unwrap().unwrap());
+
+        assert_eq!(run("step"), "cycle   1  addx 15     X=1  draws # at 0,0");
+        assert_eq!(run("step 2").lines().count(), 2);
+        assert_eq!(run("break x=21"), "added breakpoint X=21");
+        assert_eq!(
+            run("continue"),
+            "breakpoint X=21\ncycle  19  noop        X=21  draws . at 18,0"
+        );
+        run("delete");
+        run("b 40");
+        assert_eq!(
+            run("c"),
+            "breakpoint cycle 40\ncycle  40  addx 1      X=1  draws . at 39,0"
+        );
+        assert_eq!(run("screen").lines().next(), Some(&*"##..".repeat(10)));
+        run("d");
+        assert_eq!(run("continue"), "halted after 240 cycles");
+
+        assert_eq!(
+            parse_command(&INSTRUCTION_SET, "b y=1"),
+            Err("unknown register: y".to_string())
+        );
+        assert!(parse_command(&INSTRUCTION_SET, "step x").is_err());
+        assert!(parse_command(&INSTRUCTION_SET, "jump").is_err());
+        // an empty line does nothing, only quit (or the end of stdin) stops the debugger
+        assert_eq!(parse_command(&INSTRUCTION_SET, "  "), Ok(None));
+        assert_eq!(
+            parse_command(&INSTRUCTION_SET, "q"),
+            Ok(Some(Command::Quit))
+        );
+    }
+
+    /// Random program of `None` (noop) and `Some(x)` (addx), long enough to draw the whole screen
+    fn random_program(rng: &mut Rng) -> Vec<Option<i32>> {
+        let mut program = vec![];
+        let mut num_cycles = 0;
+        while num_cycles <= 240 {
+            if rng.chance(1, 3) {
+                program.push(None);
+                num_cycles += 1;
+            } else {
+                program.push(Some(rng.range_i64(-20..21) as i32));
+                num_cycles += 2;
+            }
+        }
+        program
+    }
+
+    /// Value of the register during every cycle, starting with the first
+    fn reference_register_values(program: &[Option<i32>]) -> Vec<i32> {
+        let mut register = 1;
+        let mut values = vec![];
+        for instruction in program {
+            match instruction {
+                None => values.push(register),
+                Some(x) => {
+                    values.extend([register, register]);
+                    register += x;
+                }
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_program, |program| {
+            let input = Input::new(
+                &program
+                    .iter()
+                    .map(|instruction| match instruction {
+                        None => "noop\n".to_string(),
+                        Some(x) => format!("addx {x}\n"),
+                    })
+                    .collect::<String>(),
+            );
+            let values = reference_register_values(program);
+
+            let signal_strength = [20, 60, 100, 140, 180, 220]
+                .iter()
+                .map(|&cycle| cycle as i32 * values[cycle - 1]);
+            assert_eq!(part_one(&input), Ok(signal_strength.sum()));
+
+            let every_cycle = sample_signal(
+                &mut Cpu::new(parse_program(&input).unwrap()),
+                1..=values.len() as u32,
+            )
+            .unwrap_or_else(|_| panic!("the program should run for all its cycles"));
+            let sampled: Vec<i32> = every_cycle.iter().map(|sample| sample.x).collect();
+            assert_eq!(sampled, values);
+
+            let mut screen = String::new();
+            for (pixel, sprite) in values[..240].iter().enumerate() {
+                let x = (pixel % 40) as i32;
+                screen.push(if (sprite - x).abs() <= 1 { '#' } else { '.' });
+                if x == 39 {
+                    screen.push('\n');
+                }
+            }
+            assert_eq!(part_two(&input).map(|screen| screen.bitmap), Ok(screen));
+        });
+    }
 }
@@ -1,35 +1,107 @@
-use advent_of_code::helpers::{parse_decimal_digits, parse_u64_decimal, parse_usize_decimal};
+use advent_of_code::{
+    error::{ParseError, PuzzleError},
+    helpers::{blocks, comma_list, key_value, parse_all, parse_num, ParseResult},
+    Input,
+};
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete,
-    combinator::map,
-    multi::separated_list1,
-    sequence::{pair, preceded, terminated},
+    character::complete::{char, line_ending, one_of, space0},
+    combinator::{consumed, cut, eof, map, peek, value, verify},
+    error::{context, VerboseError},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
 };
-use std::collections::VecDeque;
+use std::{cmp::Reverse, collections::VecDeque, process};
+
+/// Arithmetic on the old worry level, like `old * (old + 3)`. Only operations that keep the
+/// remainders of the divisions in the tests are supported, see `eval_modulo`. Worry levels are
+/// signed, as subtraction can take them below 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Old,
+    Literal(i64),
+    Add(Box<Expr>, Box<Expr>),
+    Subtract(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+    /** the exponent is a literal, so that powers can be taken modulo the common divisor */
+    Power(Box<Expr>, u32),
+}
+
+impl Expr {
+    /// The new worry level, or `None` if it overflows an i64
+    fn eval(&self, old: i64) -> Option<i64> {
+        match self {
+            Expr::Old => Some(old),
+            Expr::Literal(value) => Some(*value),
+            Expr::Add(a, b) => a.eval(old)?.checked_add(b.eval(old)?),
+            Expr::Subtract(a, b) => a.eval(old)?.checked_sub(b.eval(old)?),
+            Expr::Multiply(a, b) => a.eval(old)?.checked_mul(b.eval(old)?),
+            Expr::Power(base, exponent) => base.eval(old)?.checked_pow(*exponent),
+        }
+    }
 
-#[derive(Debug, Clone, Copy)]
-enum Operation {
-    ADD(Operand),
-    MULTIPLY(Operand),
+    /// The Euclidean remainder (from 0 to `modulus - 1`) of the new worry level divided by
+    /// `modulus`. This is the same as the remainder of `eval` for every divisor of `modulus`, also
+    /// for worry levels below 0, without overflowing.
+    fn eval_modulo(&self, old: i64, modulus: i64) -> i64 {
+        // the arithmetic on remainders fits in 128 bits, but a 64 bit division is much faster
+        let reduce = |value: i128| match i64::try_from(value) {
+            Ok(value) => value.rem_euclid(modulus),
+            Err(_) => value.rem_euclid(i128::from(modulus)) as i64,
+        };
+        let eval = |expr: &Expr| i128::from(expr.eval_modulo(old, modulus));
+        match self {
+            Expr::Old => old.rem_euclid(modulus),
+            Expr::Literal(value) => value.rem_euclid(modulus),
+            Expr::Add(a, b) => reduce(eval(a) + eval(b)),
+            Expr::Subtract(a, b) => reduce(eval(a) - eval(b)),
+            Expr::Multiply(a, b) => reduce(eval(a) * eval(b)),
+            Expr::Power(base, exponent) => {
+                // exponentiation by squaring
+                let (mut base, mut exponent, mut power) = (eval(base), *exponent, 1 % modulus);
+                while exponent > 0 {
+                    if exponent & 1 == 1 {
+                        power = reduce(i128::from(power) * base);
+                    }
+                    base = i128::from(reduce(base * base));
+                    exponent >>= 1;
+                }
+                power
+            }
+        }
+    }
 }
-#[derive(Debug, Clone, Copy)]
-enum Operand {
-    VALUE(u64),
-    SELF,
+
+/// The test that picks which monkey to throw to, like `divisible by 23`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    /** the worry level leaves `remainder` (the Euclidean one, never below 0) when divided by
+    `divisor`, `divisible by` means 0 */
+    Remainder {
+        divisor: i64,
+        remainder: i64,
+    },
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
 }
-impl Operation {
-    fn calc(&self, item: u64) -> u64 {
+
+impl Condition {
+    fn check(&self, worry: i64) -> bool {
         match self {
-            Operation::ADD(operand) => match operand {
-                Operand::SELF => item + item,
-                Operand::VALUE(value) => item + value,
-            },
-            Operation::MULTIPLY(operand) => match operand {
-                Operand::SELF => item * item,
-                Operand::VALUE(value) => item * value,
-            },
+            Condition::Remainder { divisor, remainder } => worry.rem_euclid(*divisor) == *remainder,
+            Condition::Not(condition) => !condition.check(worry),
+            Condition::And(a, b) => a.check(worry) && b.check(worry),
+            Condition::Or(a, b) => a.check(worry) || b.check(worry),
+        }
+    }
+
+    /// All the divisors the condition divides the worry level by
+    fn divisors(&self) -> Vec<i64> {
+        match self {
+            Condition::Remainder { divisor, .. } => vec![*divisor],
+            Condition::Not(condition) => condition.divisors(),
+            Condition::And(a, b) | Condition::Or(a, b) => [a.divisors(), b.divisors()].concat(),
         }
     }
 }
@@ -38,148 +110,417 @@ impl Operation {
 struct Monkey {
     /** which monkey to throw to: (if divisible, if not divisible) */
     throw_to: (usize, usize),
-    check_divisible_by: u64,
-    operation: Operation,
+    test: Condition,
+    operation: Expr,
     num_items_inspected: u32,
-    items: VecDeque<u64>,
+    items: VecDeque<i64>,
 }
+/// Where an item is at the start of a round: the monkey holding it, and its worry level
+type ItemState = (usize, i64);
+
 struct MonkeyGroup {
     monkeys: Vec<Monkey>,
     should_worry_decline: bool,
-    common_divisor: u64,
+    common_divisor: i64,
 }
 impl MonkeyGroup {
+    /// The monkeys must have been checked by `parse_monkeys`
     fn new(monkeys: Vec<Monkey>, should_worry_decline: bool) -> Self {
         Self {
             should_worry_decline,
-            common_divisor: monkeys
-                .iter()
-                .fold(1, |product, m| product * m.check_divisible_by),
+            common_divisor: common_divisor(&monkeys).expect("checked while parsing"),
             monkeys,
         }
     }
 
+    /// Inspect an item held by a monkey, and return its new worry level and the monkey to throw it
+    /// to. Returns `None` if the worry level overflows an i64.
+    fn inspect(&self, monkey_idx: usize, worry: i64) -> Option<(i64, usize)> {
+        let monkey = &self.monkeys[monkey_idx];
+        // do the monkey's operation
+        let worry = if self.should_worry_decline {
+            // rounded down, also below 0
+            monkey.operation.eval(worry)?.div_euclid(3)
+        } else {
+            // use the remainder to avoid overflows. this doesn't change any of the tests, but
+            // can't be combined with the division above
+            monkey.operation.eval_modulo(worry, self.common_divisor)
+        };
+        // run the test to determine which monkey to throw to
+        let target = match monkey.test.check(worry) {
+            true => monkey.throw_to.0,
+            false => monkey.throw_to.1,
+        };
+        Some((worry, target))
+    }
+
+    /// Play a round, or return `None` if a worry level overflows an i64
     fn round(&mut self) -> Option<()> {
         for monkey_idx in 0..self.monkeys.len() {
-            // we'll need to clone the monkey in order to perform mutations safely
-            let mut monkey = self.monkeys.get_mut(monkey_idx)?.clone();
-            let mut items_inspected_count: u32 = 0;
-            while let Some(mut current_item) = monkey.items.pop_front() {
-                current_item %= self.common_divisor; // use the remainder to avoid overflows
-                current_item = monkey.operation.calc(current_item); // do the monkey's operation
-                if self.should_worry_decline {
-                    current_item /= 3;
-                };
-                // check if divisible to determine which monkey to throw to
-                let is_divisible = current_item % monkey.check_divisible_by == 0;
-                let monkey_to_throw_to = match is_divisible {
-                    true => self.monkeys.get_mut(monkey.throw_to.0),
-                    false => self.monkeys.get_mut(monkey.throw_to.1),
-                }?;
-                monkey_to_throw_to.items.push_back(current_item);
-                items_inspected_count += 1;
+            let items = std::mem::take(&mut self.monkeys[monkey_idx].items);
+            self.monkeys[monkey_idx].num_items_inspected += items.len() as u32;
+            for item in items {
+                let (worry, target) = self.inspect(monkey_idx, item)?;
+                self.monkeys[target].items.push_back(worry);
             }
-            // clear the original monkey's items and update their # items inspected
-            let orig_monkey = self.monkeys.get_mut(monkey_idx)?;
-            orig_monkey.items.clear();
-            orig_monkey.num_items_inspected += items_inspected_count;
         }
         Some(())
     }
+
+    /// Follow an item through a round, from the monkey holding it at the start of the round, and
+    /// call `inspected` with every monkey that inspects it. The item keeps moving for as long as
+    /// it's thrown to monkeys that haven't had their turn yet.
+    fn item_round(
+        &self,
+        (mut monkey_idx, mut worry): ItemState,
+        mut inspected: impl FnMut(usize),
+    ) -> ItemState {
+        loop {
+            inspected(monkey_idx);
+            let (new_worry, target) = self
+                .inspect(monkey_idx, worry)
+                .expect("worry levels are kept below the common divisor");
+            worry = new_worry;
+            if target < monkey_idx {
+                return (target, worry);
+            }
+            monkey_idx = target;
+        }
+    }
+
+    /// The first round of the cycle an item gets into, and the length of the cycle, with Brent's
+    /// algorithm: it only keeps a couple of states, instead of every state so far. Returns `None`
+    /// if the item doesn't get back to an earlier state within `rounds` rounds.
+    fn find_cycle(&self, start: ItemState, rounds: u64) -> Option<(u64, u64)> {
+        let next = |state| self.item_round(state, |_| ());
+        if rounds == 0 {
+            return None;
+        }
+        // the tortoise waits at the state after a power of 2 rounds, until the hare catches up
+        let (mut tortoise, mut hare) = (start, next(start));
+        let (mut power, mut cycle_length, mut played) = (1, 1, 1);
+        while tortoise != hare {
+            if played == rounds {
+                return None;
+            }
+            if power == cycle_length {
+                tortoise = hare;
+                power *= 2;
+                cycle_length = 0;
+            }
+            hare = next(hare);
+            cycle_length += 1;
+            played += 1;
+        }
+        // with the hare a cycle ahead of the tortoise, they meet at the start of the cycle
+        let (mut tortoise, mut hare) = (start, start);
+        for _ in 0..cycle_length {
+            hare = next(hare);
+        }
+        let mut cycle_start = 0;
+        while tortoise != hare {
+            tortoise = next(tortoise);
+            hare = next(hare);
+            cycle_start += 1;
+        }
+        Some((cycle_start, cycle_length))
+    }
+
+    /// Number of items each monkey inspects in `rounds` rounds, for worry levels that don't
+    /// decline. Returns `None` if a count doesn't fit in a u64.
+    ///
+    /// Items don't affect each other, so every item is followed on its own. Its state at the
+    /// start of a round only has a limited number of values, so it eventually gets back to an
+    /// earlier state, and from there repeats the same rounds over and over. That makes the
+    /// counts for any number of rounds a matter of multiplying the counts of the cycle.
+    fn count_inspections(&self, rounds: u64) -> Option<Vec<u64>> {
+        assert!(
+            !self.should_worry_decline,
+            "worry levels must stay below the common divisor"
+        );
+        // counts of up to 2^64 rounds times the number of items can't overflow 128 bits
+        let mut counts = vec![0_u128; self.monkeys.len()];
+        for (monkey_idx, monkey) in self.monkeys.iter().enumerate() {
+            for &item in &monkey.items {
+                let start = (monkey_idx, item.rem_euclid(self.common_divisor));
+                // without a cycle, every round is played once
+                let (cycle_start, cycle_length) =
+                    self.find_cycle(start, rounds).unwrap_or((rounds, 1));
+                let (repeats, extra_rounds) = (
+                    (rounds - cycle_start) / cycle_length,
+                    (rounds - cycle_start) % cycle_length,
+                );
+                // play the rounds up to the end of the first cycle, where every round of the cycle
+                // counts for all the times it's repeated
+                let mut state = start;
+                for round in 0..rounds.min(cycle_start + cycle_length) {
+                    let times = match round.checked_sub(cycle_start) {
+                        None => 1,
+                        Some(round) => repeats + u64::from(round < extra_rounds),
+                    };
+                    state = self.item_round(state, |monkey_idx| {
+                        counts[monkey_idx] += u128::from(times);
+                    });
+                }
+            }
+        }
+        counts
+            .into_iter()
+            .map(|count| u64::try_from(count).ok())
+            .collect()
+    }
 }
 
-fn parse_operation((operator_str, operand_str): (&str, &str)) -> Option<Operation> {
-    let operand = match operand_str {
-        "old" => Operand::SELF,
-        value => Operand::VALUE(value.parse().ok()?),
-    };
-    let operation = match operator_str {
-        "+" => Operation::ADD(operand),
-        "*" => Operation::MULTIPLY(operand),
-        _ => panic!("Operation can only be + or *"),
-    };
-    Some(operation)
-}
-
-fn parse_monkey(monkey_raw_input: &str) -> Option<Monkey> {
-    let mut input_lines = monkey_raw_input.lines();
-    input_lines.next(); // ignore first line
-    let (_, starting_items) = preceded(
-        preceded(complete::space1, tag("Starting items: ")),
-        separated_list1(tag(", "), parse_u64_decimal),
-    )(input_lines.next()?)
-    .ok()?;
-    let (_, operation) = map(
-        preceded(
-            preceded(complete::space1, tag("Operation: new = old ")),
-            pair(
-                terminated(alt((tag("+"), tag("*"))), tag(" ")),
-                alt((tag("old"), parse_decimal_digits)),
+/// The product of the two highest inspection counts
+fn monkey_business(counts: impl IntoIterator<Item = u64>) -> u128 {
+    let mut counts: Vec<u64> = counts.into_iter().collect();
+    counts.sort_by_key(|&count| Reverse(count));
+    u128::from(counts[0]) * u128::from(counts[1])
+}
+
+/// Parse a sum or difference of terms, the lowest precedence
+fn parse_expr(input: &str) -> ParseResult<'_, Expr> {
+    let (mut input, mut expr) = parse_term(input)?;
+    while let Ok((rest, operator)) = operator("+-")(input) {
+        let (rest, term) = parse_term(rest)?;
+        expr = match operator {
+            '+' => Expr::Add(Box::new(expr), Box::new(term)),
+            _ => Expr::Subtract(Box::new(expr), Box::new(term)),
+        };
+        input = rest;
+    }
+    Ok((input, expr))
+}
+
+/// Parse a product of factors
+fn parse_term(input: &str) -> ParseResult<'_, Expr> {
+    let (mut input, mut expr) = parse_factor(input)?;
+    while let Ok((rest, _)) = operator("*")(input) {
+        let (rest, factor) = parse_factor(rest)?;
+        expr = Expr::Multiply(Box::new(expr), Box::new(factor));
+        input = rest;
+    }
+    Ok((input, expr))
+}
+
+/// Parse `old`, a number or an expression in parentheses, optionally raised to a power
+fn parse_factor(input: &str) -> ParseResult<'_, Expr> {
+    let (input, base) = context(
+        "old, a number or '('",
+        alt((
+            value(Expr::Old, tag("old")),
+            map(parse_num::<i64>, Expr::Literal),
+            delimited(pair(char('('), space0), parse_expr, pair(space0, char(')'))),
+        )),
+    )(input)?;
+    match operator("^")(input) {
+        Ok((rest, _)) => map(parse_num::<u32>, |exponent| {
+            Expr::Power(Box::new(base.clone()), exponent)
+        })(rest),
+        Err(_) => Ok((input, base)),
+    }
+}
+
+/// An operator out of `operators`, with optional spaces around it
+fn operator<'a>(operators: &'static str) -> impl FnMut(&'a str) -> ParseResult<'a, char> {
+    delimited(space0, one_of(operators), space0)
+}
+
+fn parse_operation(input: &str) -> ParseResult<'_, Expr> {
+    preceded(
+        tag("new = "),
+        terminated(
+            parse_expr,
+            preceded(
+                space0,
+                context("an operator (+, -, * or ^)", peek(alt((line_ending, eof)))),
             ),
         ),
-        parse_operation,
-    )(input_lines.next()?)
-    .ok()?;
-    let (_, divisible_by) = preceded(
-        preceded(complete::space1, tag("Test: divisible by ")),
-        parse_u64_decimal,
-    )(input_lines.next()?)
-    .ok()?;
-    let (_, throw_to_true) = preceded(
-        preceded(complete::space1, tag("If true: throw to monkey ")),
-        parse_usize_decimal,
-    )(input_lines.next()?)
-    .ok()?;
-    let (_, throw_to_false) = preceded(
-        preceded(complete::space1, tag("If false: throw to monkey ")),
-        parse_usize_decimal,
-    )(input_lines.next()?)
-    .ok()?;
-    Some(Monkey {
-        items: starting_items.into(),
-        num_items_inspected: 0,
-        check_divisible_by: divisible_by,
-        throw_to: (throw_to_true, throw_to_false),
-        operation: operation?,
-    })
+    )(input)
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut monkey_group =
-        MonkeyGroup::new(input.split("\n\n").map_while(parse_monkey).collect(), true);
-    for _ in 0..20 {
-        monkey_group.round();
+fn parse_divisor(input: &str) -> ParseResult<'_, i64> {
+    context("a divisor above 0", verify(parse_num::<i64>, |&d| d > 0))(input)
+}
+
+/// Parse conditions joined by `or`, the lowest precedence
+fn parse_condition(input: &str) -> ParseResult<'_, Condition> {
+    let (mut input, mut condition) = parse_all_of(input)?;
+    while let Ok((rest, _)) = tag::<_, _, VerboseError<&str>>(" or ")(input) {
+        let (rest, other) = parse_all_of(rest)?;
+        condition = Condition::Or(Box::new(condition), Box::new(other));
+        input = rest;
+    }
+    Ok((input, condition))
+}
+
+/// Parse conditions joined by `and`
+fn parse_all_of(input: &str) -> ParseResult<'_, Condition> {
+    let (mut input, mut condition) = parse_simple_condition(input)?;
+    while let Ok((rest, _)) = tag::<_, _, VerboseError<&str>>(" and ")(input) {
+        let (rest, other) = parse_simple_condition(rest)?;
+        condition = Condition::And(Box::new(condition), Box::new(other));
+        input = rest;
     }
-    let mut sorted_monkeys = monkey_group.monkeys.clone();
-    sorted_monkeys.sort_by(|a, b| b.num_items_inspected.cmp(&a.num_items_inspected));
-    let monkey_business = sorted_monkeys
+    Ok((input, condition))
+}
+
+/// Parse `divisible by <n>`, `remainder <r> modulo <n>`, `not <condition>` or a condition in
+/// parentheses
+fn parse_simple_condition(input: &str) -> ParseResult<'_, Condition> {
+    context(
+        "divisible by, remainder, not or '('",
+        alt((
+            map(
+                preceded(tag("divisible by "), cut(parse_divisor)),
+                |divisor| Condition::Remainder {
+                    divisor,
+                    remainder: 0,
+                },
+            ),
+            map(
+                preceded(
+                    tag("remainder "),
+                    cut(context(
+                        "a remainder from 0 to below the divisor",
+                        verify(
+                            separated_pair(parse_num::<i64>, tag(" modulo "), parse_divisor),
+                            |(remainder, divisor)| (0..*divisor).contains(remainder),
+                        ),
+                    )),
+                ),
+                |(remainder, divisor)| Condition::Remainder { divisor, remainder },
+            ),
+            map(
+                preceded(tag("not "), cut(parse_simple_condition)),
+                |condition| Condition::Not(Box::new(condition)),
+            ),
+            delimited(char('('), parse_condition, char(')')),
+        )),
+    )(input)
+}
+
+fn parse_monkey(input: &str) -> ParseResult<'_, Monkey> {
+    let throw_to = || preceded(tag("throw to monkey "), parse_num::<usize>);
+    map(
+        tuple((
+            delimited(tag("Monkey "), parse_num::<usize>, char(':')),
+            preceded(
+                line_ending,
+                key_value("Starting items", comma_list(parse_num::<i64>)),
+            ),
+            preceded(line_ending, key_value("Operation", parse_operation)),
+            preceded(line_ending, key_value("Test", parse_condition)),
+            preceded(line_ending, key_value("If true", throw_to())),
+            preceded(line_ending, key_value("If false", throw_to())),
+        )),
+        |(_, starting_items, operation, test, throw_to_true, throw_to_false)| Monkey {
+            items: starting_items.into(),
+            num_items_inspected: 0,
+            test,
+            throw_to: (throw_to_true, throw_to_false),
+            operation,
+        },
+    )(input)
+}
+
+/// Least common multiple of the divisors in the tests, or `None` if it doesn't fit in an i64.
+/// Monkeys often share a divisor, so this stays much smaller than the product of the divisors.
+fn common_divisor(monkeys: &[Monkey]) -> Option<i64> {
+    fn gcd(a: i64, b: i64) -> i64 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+    monkeys
         .iter()
-        .take(2)
-        .fold(1, |monkey_business, monkey| {
-            monkey_business * monkey.num_items_inspected
-        });
-    Some(monkey_business)
+        .flat_map(|monkey| monkey.test.divisors())
+        .try_fold(1_i64, |multiple, divisor| {
+            (multiple / gcd(multiple, divisor)).checked_mul(divisor)
+        })
+}
+
+/// Parse the notes, and check that every monkey throws to another monkey that exists
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let notes = parse_all(input, blocks(consumed(parse_monkey)))?;
+    for (idx, (raw, monkey)) in notes.iter().enumerate() {
+        let targets = [
+            ("If true", monkey.throw_to.0),
+            ("If false", monkey.throw_to.1),
+        ];
+        for (key, target) in targets {
+            if target >= notes.len() || target == idx {
+                let line = raw
+                    .lines()
+                    .map(str::trim_start)
+                    .find(|line| line.starts_with(key))
+                    .unwrap_or(raw);
+                let expected = format!(
+                    "a monkey to throw to other than itself (0 to {})",
+                    notes.len() - 1
+                );
+                return Err(ParseError::at(input, line, expected));
+            }
+        }
+    }
+    let monkeys: Vec<Monkey> = notes.into_iter().map(|(_, monkey)| monkey).collect();
+    if common_divisor(&monkeys).is_none() {
+        return Err(ParseError::at(
+            input,
+            input,
+            "divisors with a common multiple below 2^63",
+        ));
+    }
+    Ok(monkeys)
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    let mut monkey_group =
-        MonkeyGroup::new(input.split("\n\n").map_while(parse_monkey).collect(), false);
-    for _ in 0..10000 {
-        monkey_group.round();
+pub fn part_one(input: &Input) -> Result<u32, PuzzleError> {
+    let mut monkey_group = MonkeyGroup::new(parse_monkeys(input)?, true);
+    for _ in 0..20 {
+        monkey_group.round().ok_or_else(|| {
+            PuzzleError::Unsolvable("a worry level doesn't fit in 64 bits".to_string())
+        })?;
     }
-    let mut sorted_monkeys = monkey_group.monkeys.clone();
-    sorted_monkeys.sort_by(|a, b| b.num_items_inspected.cmp(&a.num_items_inspected));
-    let monkey_business: u64 = sorted_monkeys
+    let counts = monkey_group
+        .monkeys
         .iter()
-        .take(2)
-        .fold(1, |monkey_business, monkey| {
-            monkey_business * monkey.num_items_inspected as u64
-        });
-    Some(monkey_business)
+        .map(|monkey| u64::from(monkey.num_items_inspected));
+    u32::try_from(monkey_business(counts)).map_err(|_| {
+        PuzzleError::Unsolvable("the monkey business doesn't fit in 32 bits".to_string())
+    })
+}
+
+/// The monkey business after any number of rounds, where worry levels don't decline
+fn monkey_business_after(input: &str, rounds: u64) -> Result<u128, PuzzleError> {
+    let monkey_group = MonkeyGroup::new(parse_monkeys(input)?, false);
+    let counts = monkey_group.count_inspections(rounds).ok_or_else(|| {
+        PuzzleError::Unsolvable("an inspection count doesn't fit in 64 bits".to_string())
+    })?;
+    Ok(monkey_business(counts))
+}
+
+pub fn part_two(input: &Input) -> Result<u64, PuzzleError> {
+    u64::try_from(monkey_business_after(input, 10000)?).map_err(|_| {
+        PuzzleError::Unsolvable("the monkey business doesn't fit in 64 bits".to_string())
+    })
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 11);
+    let mut args = pico_args::Arguments::from_env();
+    // `cargo solve 11 -- --rounds 1000000000000` plays part two for longer
+    let rounds: Option<u64> = args.opt_value_from_str("--rounds").unwrap_or_else(|e| {
+        eprintln!("Failed to process arguments: {e}");
+        process::exit(1);
+    });
+    if let Some(rounds) = rounds {
+        match monkey_business_after(input, rounds) {
+            Ok(business) => println!("monkey business after {rounds} rounds: {business}"),
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -187,16 +528,391 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 11);
-        assert_eq!(part_one(&input), Some(10605));
+        assert_eq!(part_one(&input), Ok(10605));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 11);
-        assert_eq!(part_two(&input), Some(2713310158));
+        assert_eq!(part_two(&input), Ok(2713310158));
+    }
+
+    #[test]
+    fn test_part_one_divides_before_reducing() {
+        // 10 / 3 is divisible by 3, but 10 reduced by the common divisor (6) first isn't
+        let input = Input::new(
+            "\
+Monkey 0:
+  Starting items: 10
+  Operation: new = old
+  Test: divisible by 3
+    If true: throw to monkey 1
+    If false: throw to monkey 2
+
+Monkey 1:
+  Starting items: 0
+  Operation: new = old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 0
+  Operation: new = old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+",
+        );
+        assert_eq!(part_one(&input), Ok(58 * 59));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        // the second monkey has a broken operation, which must not be silently skipped
+        let input = Input::new(
+            &advent_of_code::read_file("examples", 11).replacen("old + 6", "old / 6", 1),
+        );
+        let error = part_one(&input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected an operator (+, -, * or ^) at line 10, column 24 (in Operation)\n \
+             10 |   Operation: new = old / 6\n    \
+                |                        ^"
+        );
+    }
+
+    #[test]
+    fn test_invalid_monkeys() {
+        let example = advent_of_code::read_file("examples", 11);
+        let input = Input::new(&example.replacen("throw to monkey 2", "throw to monkey 4", 1));
+        let error = parse_monkeys(&input).unwrap_err();
+        assert_eq!((error.line, error.column), (5, 5));
+
+        let input = Input::new(&example.replacen("divisible by 23", "divisible by 0", 1));
+        let error = parse_monkeys(&input).unwrap_err();
+        assert_eq!((error.line, error.column), (4, 22));
+
+        // worry levels can go below 0, only overflowing is an error
+        let input = Input::new(&example.replacen("old + 6", "old - 100", 1));
+        assert!(part_one(&input).is_ok());
+        let input = Input::new(&example.replacen("old * old", "old ^ 40", 1));
+        assert_eq!(
+            part_one(&input),
+            Err(PuzzleError::Unsolvable(
+                "a worry level doesn't fit in 64 bits".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        let eval = |expr: &str, old: i64| parse_all(expr, parse_expr).unwrap().eval(old);
+        assert_eq!(eval("old * 19", 2), Some(38));
+        assert_eq!(eval("old + 2 * 3", 1), Some(7));
+        assert_eq!(eval("(old + 2) * 3", 1), Some(9));
+        assert_eq!(eval("old*old-1", 3), Some(8));
+        assert_eq!(eval("10 - old - 2", 3), Some(5));
+        assert_eq!(eval("2 * old ^ 3 + 1", 2), Some(17));
+        assert_eq!(eval("(old + 1) ^ 2", 2), Some(9));
+        assert_eq!(eval("old - 5", 3), Some(-2));
+        assert_eq!(eval("old * -2", 3), Some(-6));
+        assert_eq!(eval("old ^ 2", i64::MAX), None);
+        assert_eq!(eval("old - 1", i64::MIN), None);
+        assert!(parse_all("old +", parse_expr).is_err());
+        assert!(parse_all("(old * 2", parse_expr).is_err());
+        assert!(parse_all("old ^ old", parse_expr).is_err());
+    }
+
+    /// Random expression, written with all its parentheses
+    fn random_expr(rng: &mut Rng, depth: u32) -> String {
+        if depth == 0 || rng.chance(1, 3) {
+            return match rng.chance(1, 2) {
+                true => "old".to_string(),
+                false => rng.range(0..30).to_string(),
+            };
+        }
+        let a = random_expr(rng, depth - 1);
+        match rng.range(0..4) {
+            0 => format!("({a} + {})", random_expr(rng, depth - 1)),
+            1 => format!("({a} - {})", random_expr(rng, depth - 1)),
+            2 => format!("({a} * {})", random_expr(rng, depth - 1)),
+            _ => format!("({a} ^ {})", rng.range(0..5)),
+        }
+    }
+
+    #[test]
+    fn test_eval_modulo() {
+        prop::check(
+            |rng| {
+                let modulus = rng.range_i64(1..10_000);
+                (random_expr(rng, 4), rng.range_i64(-1000..1000), modulus)
+            },
+            |(expr, old, modulus)| {
+                let expr = parse_all(expr, parse_expr).unwrap();
+                if let Some(value) = expr.eval(*old) {
+                    assert_eq!(expr.eval_modulo(*old, *modulus), value.rem_euclid(*modulus));
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn test_conditions() {
+        let check = |condition: &str, worry: i64| {
+            parse_all(condition, parse_condition).unwrap().check(worry)
+        };
+        assert!(check("divisible by 23", 46));
+        assert!(!check("divisible by 23", 47));
+        assert!(check("remainder 2 modulo 5", 12));
+        assert!(check("remainder 2 modulo 5", -3));
+        assert!(check("divisible by 3", -6));
+        assert!(check("not divisible by 2", 3));
+        assert!(check("divisible by 2 and divisible by 3", 12));
+        assert!(!check("divisible by 2 and divisible by 3", 8));
+        assert!(check(
+            "divisible by 7 or divisible by 2 and divisible by 3",
+            7
+        ));
+        assert!(!check(
+            "(divisible by 7 or divisible by 2) and divisible by 3",
+            7
+        ));
+        assert!(check("not (divisible by 2 or divisible by 3)", 5));
+
+        let condition = parse_all(
+            "divisible by 6 or remainder 1 modulo 4 and not divisible by 9",
+            parse_condition,
+        )
+        .unwrap();
+        assert_eq!(condition.divisors(), [6, 4, 9]);
+
+        assert!(parse_all("divisible by 0", parse_condition).is_err());
+        assert!(parse_all("remainder 5 modulo 5", parse_condition).is_err());
+        assert!(parse_all("remainder -1 modulo 5", parse_condition).is_err());
+        assert!(parse_all("divisible by 2 xor divisible by 3", parse_condition).is_err());
+    }
+
+    #[test]
+    fn test_custom_monkeys() {
+        // every divisor of the tests counts towards the common divisor
+        let input = Input::new(
+            &advent_of_code::read_file("examples", 11)
+                .replacen("old * 19", "(old + 1) * 2 - 1", 1)
+                .replacen(
+                    "divisible by 19",
+                    "remainder 2 modulo 7 or divisible by 19",
+                    1,
+                )
+                .replacen("divisible by 13", "not divisible by 4", 1),
+        );
+        let monkeys = parse_monkeys(&input).unwrap();
+        assert_eq!(common_divisor(&monkeys), Some(23 * 7 * 19 * 4 * 17));
+        assert!(part_one(&input).is_ok());
+        assert!(part_two(&input).is_ok());
+    }
+
+    #[test]
+    fn test_common_divisor() {
+        // shared factors are only counted once
+        let input = Input::new(
+            &advent_of_code::read_file("examples", 11)
+                .replacen("divisible by 23", "divisible by 6", 1)
+                .replacen("divisible by 19", "divisible by 4", 1)
+                .replacen("divisible by 13", "divisible by 9", 1)
+                .replacen("divisible by 17", "divisible by 6", 1),
+        );
+        let monkeys = parse_monkeys(&input).unwrap();
+        assert_eq!(common_divisor(&monkeys), Some(36));
+    }
+
+    #[test]
+    fn test_count_inspections() {
+        // following single items through their cycles must add up to playing every round
+        prop::check(
+            |rng| (random_monkeys(rng), rng.range(0..300) as u32),
+            |(monkeys, rounds)| {
+                let monkeys = parse_monkeys(&monkeys_input(monkeys)).unwrap();
+                let cycles = MonkeyGroup::new(monkeys.clone(), false);
+                let mut brute_force = MonkeyGroup::new(monkeys, false);
+                for _ in 0..*rounds {
+                    brute_force.round().unwrap();
+                }
+                let expected: Vec<u64> = brute_force
+                    .monkeys
+                    .iter()
+                    .map(|monkey| u64::from(monkey.num_items_inspected))
+                    .collect();
+                assert_eq!(cycles.count_inspections(u64::from(*rounds)), Some(expected));
+            },
+        );
+    }
+
+    #[test]
+    fn test_many_rounds() {
+        let input = advent_of_code::read_file("examples", 11);
+        assert_eq!(monkey_business_after(&input, 20), Ok(99 * 103));
+        assert_eq!(monkey_business_after(&input, 1000), Ok(5204 * 5192));
+
+        // the example as a whole gets back to its state after round 175 every 76608 rounds, so
+        // the counts after 10^12 rounds follow from playing fewer than 80000 rounds
+        let (cycle_start, cycle_length) = (175, 76608);
+        let rounds: u64 = 1_000_000_000_000;
+        let (repeats, extra_rounds) = (
+            (rounds - cycle_start) / cycle_length,
+            (rounds - cycle_start) % cycle_length,
+        );
+        let mut group = MonkeyGroup::new(parse_monkeys(&input).unwrap(), false);
+        let snapshot = |group: &MonkeyGroup| -> (Vec<Vec<i64>>, Vec<u64>) {
+            let items = group.monkeys.iter().map(|monkey| {
+                let mut items: Vec<i64> = monkey.items.iter().copied().collect();
+                items.sort();
+                items
+            });
+            let counts = group
+                .monkeys
+                .iter()
+                .map(|monkey| monkey.num_items_inspected);
+            (items.collect(), counts.map(u64::from).collect())
+        };
+        let mut snapshots = vec![];
+        for round in 1..=cycle_start + cycle_length {
+            group.round().unwrap();
+            if [
+                cycle_start,
+                cycle_start + extra_rounds,
+                cycle_start + cycle_length,
+            ]
+            .contains(&round)
+            {
+                snapshots.push(snapshot(&group));
+            }
+        }
+        let [(start_items, start_counts), (_, extra_counts), (end_items, end_counts)] =
+            &snapshots[..]
+        else {
+            panic!("three snapshots are taken");
+        };
+        assert_eq!(start_items, end_items);
+        let counts = (0..4).map(|monkey| {
+            extra_counts[monkey] + repeats * (end_counts[monkey] - start_counts[monkey])
+        });
+        let business = monkey_business(counts);
+        assert_eq!(business, 5217653508757 * 5202028508760);
+        assert_eq!(monkey_business_after(&input, rounds), Ok(business));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(11, |input| part_one(input).and(part_two(input)));
+    }
+
+    #[derive(Debug, Clone)]
+    struct RandomMonkey {
+        items: Vec<i64>,
+        /** (operator, operand), where a missing operand means 'old' */
+        operation: (char, Option<i64>),
+        divisor: i64,
+        throw_to: (usize, usize),
+    }
+
+    fn random_monkeys(rng: &mut Rng) -> Vec<RandomMonkey> {
+        let num_monkeys = rng.range(2..6);
+        (0..num_monkeys)
+            .map(|id| {
+                let mut other_monkey = || (id + rng.range(1..num_monkeys)) % num_monkeys;
+                let throw_to = (other_monkey(), other_monkey());
+                RandomMonkey {
+                    items: (0..rng.range(1..5))
+                        .map(|_| rng.range_i64(50..100))
+                        .collect(),
+                    operation: (
+                        *rng.choose(&['+', '-', '*']),
+                        (!rng.chance(1, 5)).then(|| rng.range_i64(1..20)),
+                    ),
+                    divisor: *rng.choose(&[2, 3, 5, 7, 11, 13, 17, 19, 23]),
+                    throw_to,
+                }
+            })
+            .collect()
+    }
+
+    fn monkeys_input(monkeys: &[RandomMonkey]) -> Input {
+        let notes: Vec<String> = monkeys
+            .iter()
+            .enumerate()
+            .map(|(id, monkey)| {
+                let items: Vec<String> = monkey.items.iter().map(i64::to_string).collect();
+                let (operator, operand) = monkey.operation;
+                let operand = operand.map_or("old".to_string(), |value| value.to_string());
+                format!(
+                    "Monkey {id}:\n  Starting items: {}\n  Operation: new = old {operator} {operand}\n  \
+                     Test: divisible by {}\n    If true: throw to monkey {}\n    \
+                     If false: throw to monkey {}\n",
+                    items.join(", "),
+                    monkey.divisor,
+                    monkey.throw_to.0,
+                    monkey.throw_to.1
+                )
+            })
+            .collect();
+        Input::new(&notes.join("\n"))
+    }
+
+    /// Play the rounds on the full worry levels, or modulo `modulus` if given. Returns `None` if
+    /// the worry levels overflow an i64.
+    fn reference_monkey_business(
+        monkeys: &[RandomMonkey],
+        rounds: usize,
+        modulus: Option<i64>,
+    ) -> Option<u64> {
+        let mut items: Vec<Vec<i64>> = monkeys.iter().map(|m| m.items.clone()).collect();
+        let mut inspected = vec![0_u64; monkeys.len()];
+        for _ in 0..rounds {
+            for (id, monkey) in monkeys.iter().enumerate() {
+                for item in std::mem::take(&mut items[id]) {
+                    let operand = monkey.operation.1.unwrap_or(item);
+                    let worry = match monkey.operation.0 {
+                        '+' => item.checked_add(operand)?,
+                        '-' => item.checked_sub(operand)?,
+                        _ => item.checked_mul(operand)?,
+                    };
+                    let worry = match modulus {
+                        Some(modulus) => worry.rem_euclid(modulus),
+                        None => worry.div_euclid(3),
+                    };
+                    let target = match worry.rem_euclid(monkey.divisor) == 0 {
+                        true => monkey.throw_to.0,
+                        false => monkey.throw_to.1,
+                    };
+                    items[target].push(worry);
+                    inspected[id] += 1;
+                }
+            }
+        }
+        inspected.sort();
+        Some(inspected.iter().rev().take(2).product())
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_monkeys, |monkeys| {
+            let input = monkeys_input(monkeys);
+            // without the modulus, squaring quickly overflows: those cases can't be compared
+            if let Some(expected) = reference_monkey_business(monkeys, 20, None) {
+                if expected <= u64::from(u32::MAX) {
+                    assert_eq!(part_one(&input), Ok(expected as u32));
+                }
+            }
+
+            let modulus = monkeys.iter().map(|m| m.divisor).product();
+            let expected = reference_monkey_business(monkeys, 10000, Some(modulus));
+            assert_eq!(part_two(&input).ok(), expected);
+        });
     }
 }
@@ -1,6 +1,10 @@
-use std::collections::{HashMap, HashSet};
-
-use advent_of_code::grid::{Grid, GridCoord};
+use advent_of_code::{
+    error::{ParseError, PuzzleError},
+    grid::{Grid, GridCoord},
+    search::bfs,
+    Input,
+};
+use std::process;
 
 #[derive(Debug, Clone, Hash, Copy, PartialEq, Eq)]
 enum Square {
@@ -23,21 +27,51 @@ impl Square {
     }
 }
 
-fn parse_grid(input: &str) -> Option<Grid<Square>> {
-    let mut input_lines = input.lines().peekable();
-    let mut grid = Grid::new(input_lines.peek()?.chars().count(), input_lines.count());
+fn parse_grid(input: &str) -> Result<Grid<Square>, ParseError> {
+    let width = input.lines().next().unwrap_or_default().chars().count();
+    let mut grid = Grid::new(width, input.lines().count());
 
     for (y, line) in input.lines().enumerate() {
-        for (x, char) in line.chars().enumerate() {
+        for (x, (position, char)) in line.char_indices().enumerate() {
             let square = match char {
                 'S' => Square::Start(0),
                 'E' => Square::End(25),
-                char => Square::Normal(char as u8 - 'a' as u8),
+                'a'..='z' => Square::Normal(char as u8 - b'a'),
+                _ => {
+                    return Err(ParseError::at(
+                        input,
+                        &line[position..],
+                        "an elevation (a-z), S or E",
+                    ))
+                }
             };
-            grid.insert_cell((x, y).into(), square)?;
+            grid.insert_cell((x, y).into(), square)
+                .ok_or_else(|| ParseError::at(input, &line[position..], "the end of the row"))?;
+        }
+        if line.chars().count() < width {
+            return Err(ParseError::at(
+                input,
+                &line[line.len()..],
+                "more squares (rows must have the same length)",
+            ));
         }
     }
-    Some(grid)
+    Ok(grid)
+}
+
+/// Fewest steps to the end square, or an error if it can't be reached
+fn fewest_steps(
+    grid: &Grid<Square>,
+    start_coords: impl IntoIterator<Item = GridCoord>,
+) -> Result<u32, PuzzleError> {
+    let result = bfs(
+        start_coords,
+        |coord| find_walkable_neighbors(grid, coord),
+        |coord| grid.get_cell(coord) == Some(&Square::End(25)),
+    )
+    .ok_or_else(|| PuzzleError::Unsolvable("there's no path to the end square (E)".to_string()))?;
+
+    Ok(u32::try_from(result.cost).expect("fewer steps than squares"))
 }
 
 fn find_walkable_neighbors(grid: &Grid<Square>, coord: &GridCoord) -> Vec<GridCoord> {
@@ -60,82 +94,57 @@ fn find_walkable_neighbors(grid: &Grid<Square>, coord: &GridCoord) -> Vec<GridCo
         .collect()
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
+pub fn part_one(input: &Input) -> Result<u32, PuzzleError> {
     let grid = parse_grid(input)?;
-    let mut visited_coords: HashMap<GridCoord, Option<GridCoord>> = HashMap::new();
-    let mut current_coords: HashSet<GridCoord> = HashSet::new();
-
     let start_coords = grid
         .iter_all_cells()
-        .find(|(_, square)| **square == Square::Start(0))?
-        .0;
-    let end_coords = grid
-        .iter_all_cells()
-        .find(|(_, square)| **square == Square::End(25))?
+        .find(|(_, square)| **square == Square::Start(0))
+        .ok_or_else(|| PuzzleError::Unsolvable("there's no start square (S)".to_string()))?
         .0;
-    current_coords.insert(start_coords.clone());
-    visited_coords.insert(start_coords.clone(), None);
-
-    let mut num_steps = 0;
-    while !visited_coords.contains_key(end_coords) {
-        let mut next_coords: HashSet<GridCoord> = HashSet::new();
-        for current_coord in &current_coords {
-            for new_coord in find_walkable_neighbors(&grid, current_coord) {
-                if visited_coords.contains_key(&new_coord) {
-                    continue;
-                }
-                visited_coords.insert(new_coord, Some(current_coord.clone()));
-                next_coords.insert(new_coord);
-            }
-        }
-        current_coords = next_coords;
-        num_steps += 1;
-    }
 
-    Some(num_steps)
+    fewest_steps(&grid, [start_coords])
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &Input) -> Result<u32, PuzzleError> {
     let grid = parse_grid(input)?;
-    let mut visited_coords: HashMap<GridCoord, Option<GridCoord>> = HashMap::new();
-    let mut current_coords: HashSet<GridCoord> = HashSet::from_iter(
-        grid.iter_all_cells()
-            .filter_map(|(coords, square)| match *square {
-                Square::Start(0) => Some(*coords),
-                Square::Normal(0) => Some(*coords),
-                _ => None,
-            }),
-    );
-    let end_coords = grid
+    let start_coords = grid
         .iter_all_cells()
-        .find(|(_, square)| **square == Square::End(25))?
-        .0;
+        .filter_map(|(coords, square)| match *square {
+            Square::Start(0) => Some(coords),
+            Square::Normal(0) => Some(coords),
+            _ => None,
+        });
 
-    for coords in &current_coords {
-        visited_coords.insert(coords.clone(), None);
-    }
-
-    let mut num_steps = 0;
-    while !visited_coords.contains_key(end_coords) {
-        let mut next_coords: HashSet<GridCoord> = HashSet::new();
-        for current_coord in &current_coords {
-            for new_coord in find_walkable_neighbors(&grid, current_coord) {
-                if visited_coords.contains_key(&new_coord) {
-                    continue;
-                }
-                visited_coords.insert(new_coord, Some(current_coord.clone()));
-                next_coords.insert(new_coord);
-            }
-        }
-        current_coords = next_coords;
-        num_steps += 1;
-    }
+    fewest_steps(&grid, start_coords)
+}
 
-    Some(num_steps)
+/// Save the heightmap as a grayscale image, with the start square in green and the end in red
+fn save_heightmap(grid: &Grid<Square>, path: &str) -> std::io::Result<()> {
+    grid.to_image(|square| match square {
+        Square::Start(_) => [0, 255, 0],
+        Square::End(_) => [255, 0, 0],
+        Square::Normal(elev) => [elev * 10; 3],
+    })
+    .save(path)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 12);
+    let mut args = pico_args::Arguments::from_env();
+    // `cargo solve 12 -- --image heightmap.png` saves the heightmap (.png or .ppm)
+    let image: Option<String> = args.opt_value_from_str("--image").unwrap_or_else(|e| {
+        eprintln!("Failed to process arguments: {e}");
+        process::exit(1);
+    });
+    if let Some(path) = image {
+        match parse_grid(input) {
+            Ok(grid) => match save_heightmap(&grid, &path) {
+                Ok(()) => println!("saved the heightmap to {path}"),
+                Err(e) => eprintln!("error: couldn't save {path}: {e}"),
+            },
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -143,16 +152,147 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 12);
-        assert_eq!(part_one(&input), Some(31));
+        assert_eq!(part_one(&input), Ok(31));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 12);
-        assert_eq!(part_two(&input), Some(29));
+        assert_eq!(part_two(&input), Ok(29));
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let input = advent_of_code::read_file("examples", 12);
+        let grid = parse_grid(&input).unwrap();
+        let result = bfs(
+            [GridCoord::from((0, 0))],
+            |coord| find_walkable_neighbors(&grid, coord),
+            |coord| grid.get_cell(coord) == Some(&Square::End(25)),
+        )
+        .unwrap();
+        let path = result.path();
+        assert_eq!(path.len(), 32);
+        assert_eq!(path.first(), Some(&GridCoord::from((0, 0))));
+        assert_eq!(path.last(), Some(&GridCoord::from((5, 2))));
+        assert!(path.windows(2).all(|step| {
+            let elevations = step
+                .iter()
+                .map(|coord| grid.get_cell(coord).unwrap().elev());
+            let [from, to]: [u8; 2] = elevations.collect::<Vec<u8>>().try_into().unwrap();
+            to <= from + 1
+        }));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        for (input, position) in [
+            ("Sab\nc1E", (2, 2)),
+            ("Sab\ncE", (2, 3)),
+            ("Sa\ncbE", (2, 3)),
+        ] {
+            let error = parse_grid(input).unwrap_err();
+            assert_eq!((error.line, error.column), position, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(12, |input| part_one(input).and(part_two(input)));
+    }
+
+    /// Random heightmap that mostly rises towards the bottom right, with noise so that some
+    /// squares can't be climbed
+    fn random_heightmap(rng: &mut Rng) -> Vec<Vec<u8>> {
+        let (width, height) = (rng.range(2..10), rng.range(2..10));
+        let mut heightmap: Vec<Vec<u8>> = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let slope = (x + y) * 25 / (width + height - 2);
+                        let noise = rng.range_i64(-2..3);
+                        b'a' + (slope as i64 + noise).clamp(0, 25) as u8
+                    })
+                    .collect()
+            })
+            .collect();
+        let random_square = |rng: &mut Rng| (rng.range(0..width), rng.range(0..height));
+        let (start_x, start_y) = random_square(rng);
+        let (end_x, end_y) = random_square(rng);
+        heightmap[start_y][start_x] = b'S';
+        heightmap[end_y][end_x] = b'E';
+        heightmap
+    }
+
+    /// Fewest steps from any of the start squares to E, by relaxing the distances of all squares
+    /// until nothing changes anymore
+    fn reference_fewest_steps(heightmap: &[Vec<u8>], is_start: impl Fn(u8) -> bool) -> Option<u32> {
+        let elevation = |square: u8| match square {
+            b'S' => 0,
+            b'E' => 25,
+            square => square - b'a',
+        };
+        let mut steps: Vec<Vec<Option<u32>>> = heightmap
+            .iter()
+            .map(|row| row.iter().map(|&sq| is_start(sq).then_some(0)).collect())
+            .collect();
+        let (width, height) = (heightmap[0].len(), heightmap.len());
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for y in 0..height {
+                for x in 0..width {
+                    let neighbors = [
+                        (x + 1, y),
+                        (x.wrapping_sub(1), y),
+                        (x, y + 1),
+                        (x, y.wrapping_sub(1)),
+                    ];
+                    for (nx, ny) in neighbors {
+                        if nx >= width || ny >= height {
+                            continue;
+                        }
+                        let can_climb =
+                            elevation(heightmap[y][x]) <= elevation(heightmap[ny][nx]) + 1;
+                        if let (Some(from), true) = (steps[ny][nx], can_climb) {
+                            if steps[y][x].is_none_or(|current| from + 1 < current) {
+                                steps[y][x] = Some(from + 1);
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let (end_y, end_row) = heightmap
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.contains(&b'E'))?;
+        let end_x = end_row.iter().position(|&square| square == b'E')?;
+        steps[end_y][end_x]
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_heightmap, |heightmap| {
+            let rows: Vec<&str> = heightmap
+                .iter()
+                .map(|row| std::str::from_utf8(row).unwrap())
+                .collect();
+            let input = Input::new(&rows.join("\n"));
+            assert_eq!(
+                part_one(&input).ok(),
+                reference_fewest_steps(heightmap, |square| square == b'S')
+            );
+            assert_eq!(
+                part_two(&input).ok(),
+                reference_fewest_steps(heightmap, |square| square == b'S' || square == b'a')
+            );
+        });
     }
 }
@@ -1,92 +1,489 @@
-use serde::Deserialize;
-use std::fmt;
+use advent_of_code::{error::ParseError, Input};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Write},
+    process,
+};
 
-#[derive(Deserialize, Clone, PartialEq, Eq)]
-#[serde(untagged)]
-enum PacketData {
-    Value(u8),
-    List(Vec<PacketData>),
+/// A packet, kept as the text it was parsed from. Packets are compared by walking through the
+/// text of both, so neither parsing nor comparing them allocates, and integers can be of any
+/// size.
+#[derive(Clone, Copy)]
+struct Packet<'a>(&'a str);
+
+impl fmt::Debug for Packet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// With `{:#}`, lists that hold other lists are spread over several lines
+impl fmt::Display for Packet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write_pretty(f, self.0, 0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+fn write_pretty(f: &mut fmt::Formatter<'_>, item: &str, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    // integers and lists of integers fit on one line
+    if item.matches('[').count() <= 1 {
+        return write!(f, "{indent}{item}");
+    }
+    writeln!(f, "{indent}[")?;
+    let mut items = items(item).peekable();
+    while let Some(item) = items.next() {
+        write_pretty(f, item, depth + 1)?;
+        writeln!(f, "{}", if items.peek().is_some() { "," } else { "" })?;
+    }
+    write!(f, "{indent}]")
+}
+
+/// The items of a list, like `1` and `[2,3]` for `[1,[2,3]]`
+fn items(list: &str) -> impl Iterator<Item = &str> {
+    let mut rest = &list[1..list.len() - 1];
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut depth = 0;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    _ => {}
+                }
+                c == ',' && depth == 0
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let item = &rest[..end];
+        rest = rest.get(end + 1..).unwrap_or_default();
+        Some(item)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Integer(&'a str),
+}
+
+/// The tokens of a packet. An integer that is compared with a list can be promoted to a list
+/// holding only that integer, without changing the text.
+struct Tokens<'a> {
+    packet: &'a str,
+    rest: &'a str,
+    /** integer to return next, after it was promoted to a list */
+    promoted: Option<&'a str>,
+    /** closing brackets of promoted integers, returned after the integer */
+    closes: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(packet: Packet<'a>) -> Self {
+        Self {
+            packet: packet.0,
+            rest: packet.0,
+            promoted: None,
+            closes: 0,
+        }
+    }
+
+    /// The text of the list whose opening bracket was just returned
+    fn list(&self) -> &'a str {
+        let start = self.packet.len() - self.rest.len() - 1;
+        let mut depth = 0;
+        let end = self.packet[start..]
+            .bytes()
+            .position(|c| {
+                match c {
+                    b'[' => depth += 1,
+                    b']' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .map_or(self.packet.len(), |end| start + end + 1);
+        &self.packet[start..end]
+    }
+
+    /// Continue as if `integer` (which was just returned) had been `[integer]`, and its opening
+    /// bracket was just returned
+    fn promote(&mut self, integer: &'a str) {
+        self.promoted = Some(integer);
+        self.closes += 1;
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if let Some(integer) = self.promoted.take() {
+            return Some(Token::Integer(integer));
+        }
+        if self.closes > 0 {
+            self.closes -= 1;
+            return Some(Token::Close);
+        }
+        self.rest = self.rest.trim_start_matches(',');
+        let token = match self.rest.as_bytes().first()? {
+            b'[' => Token::Open,
+            b']' => Token::Close,
+            _ => {
+                let len = self
+                    .rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(self.rest.len());
+                let (integer, rest) = self.rest.split_at(len);
+                self.rest = rest;
+                return Some(Token::Integer(integer));
+            }
+        };
+        self.rest = &self.rest[1..];
+        Some(token)
+    }
+}
+
+/// Compare integers of any size, given as their decimal digits
+fn compare_integers(left: &str, right: &str) -> Ordering {
+    let (left, right) = (left.trim_start_matches('0'), right.trim_start_matches('0'));
+    left.len().cmp(&right.len()).then_with(|| left.cmp(right))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
 }
 
-impl fmt::Debug for PacketData {
+/// An item of a packet while comparing, which can be an integer promoted to a list
+#[derive(Debug, Clone, Copy)]
+enum Item<'a> {
+    Text(&'a str),
+    Promoted(&'a str),
+}
+
+impl fmt::Display for Item<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Value(v) => write!(f, "{v}"),
-            Self::List(list) => f.debug_list().entries(list).finish(),
-        }
-    }
-}
-
-fn parse_packets(input: &str) -> Option<Vec<[PacketData; 2]>> {
-    let mut packet_pairs: Vec<[PacketData; 2]> = vec![];
-    let raw_lines = input.lines().collect::<Vec<&str>>();
-    let mut raw_packet_pairs = raw_lines.chunks(3).clone();
-    while let Some(raw_packet_pair) = raw_packet_pairs.next() {
-        let packet0: Vec<PacketData> = serde_json::from_str(raw_packet_pair.get(0)?).ok()?;
-        let packet1: Vec<PacketData> = serde_json::from_str(raw_packet_pair.get(1)?).ok()?;
-        packet_pairs.push([PacketData::List(packet0), PacketData::List(packet1)]);
-    }
-    Some(packet_pairs)
-}
-
-fn validate(left: &PacketData, right: &PacketData) -> bool {
-    match (left, right) {
-        (PacketData::Value(left_value), PacketData::Value(right_value)) => {
-            left_value < right_value
-        }
-        (PacketData::Value(left_value), PacketData::List(right_list)) => validate(
-            &PacketData::List(vec![PacketData::Value(*left_value)]),
-            &PacketData::List(right_list.clone()),
-        ),
-        (PacketData::List(left_list), PacketData::Value(right_value)) => validate(
-            &PacketData::List(left_list.clone()),
-            &PacketData::List(vec![PacketData::Value(*right_value)]),
-        ),
-        (PacketData::List(left_list), PacketData::List(right_list)) => {
-            if left_list.len() == 0 {
-                return true;
-            }
-            let mut validated = true;
-            for (i, left_item) in left_list.iter().enumerate() {
-                match right_list.get(i) {
-                    None => {
-                        break;
-                    },
-                    Some(right_item) => {
-                        if validate(left_item, right_item) {
-                            break;
-                        } else {
-                            validated = false;
-                            break;
-                        }
-                    },
+            Item::Text(text) => write!(f, "{text}"),
+            Item::Promoted(integer) => write!(f, "[{integer}]"),
+        }
+    }
+}
+
+/// A step of comparing two packets, to explain the comparison
+#[derive(Debug, Clone, Copy)]
+enum Step<'a> {
+    /** two items are compared, and for lists their items next */
+    Compare(Item<'a>, Item<'a>),
+    /** the integer on one side is compared as a list holding only that integer */
+    Convert(Side, &'a str),
+    /** both lists ran out of items together, so the items after them are compared */
+    EndOfLists,
+    /** the integer on one side is smaller, which decides the ordering */
+    Smaller(Side),
+    /** the list on one side ran out of items first, which decides the ordering */
+    RanOut(Side),
+}
+
+/// Compare packets like the puzzle says: integers by value, lists item by item and then by
+/// length, and an integer compared with a list as if it were a list holding only that integer.
+/// Every step is passed to `on_step`, if there is one.
+fn compare<'a>(
+    left: Packet<'a>,
+    right: Packet<'a>,
+    mut on_step: Option<&mut dyn FnMut(Step<'a>)>,
+) -> Ordering {
+    // steps are only made when they are used, as finding the text of a list takes time
+    let mut log = |step: &dyn Fn() -> Step<'a>| {
+        if let Some(on_step) = on_step.as_mut() {
+            on_step(step());
+        }
+    };
+    let (mut left, mut right) = (Tokens::new(left), Tokens::new(right));
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(Token::Open), Some(Token::Open)) => {
+                log(&|| Step::Compare(Item::Text(left.list()), Item::Text(right.list())))
+            }
+            (Some(Token::Close), Some(Token::Close)) => log(&|| Step::EndOfLists),
+            (Some(Token::Integer(l)), Some(Token::Integer(r))) => {
+                log(&|| Step::Compare(Item::Text(l), Item::Text(r)));
+                match compare_integers(l, r) {
+                    Ordering::Equal => {}
+                    Ordering::Less => {
+                        log(&|| Step::Smaller(Side::Left));
+                        return Ordering::Less;
+                    }
+                    Ordering::Greater => {
+                        log(&|| Step::Smaller(Side::Right));
+                        return Ordering::Greater;
+                    }
                 }
             }
-            validated
+            (Some(Token::Integer(l)), Some(Token::Open)) => {
+                log(&|| Step::Compare(Item::Text(l), Item::Text(right.list())));
+                log(&|| Step::Convert(Side::Left, l));
+                log(&|| Step::Compare(Item::Promoted(l), Item::Text(right.list())));
+                left.promote(l);
+            }
+            (Some(Token::Open), Some(Token::Integer(r))) => {
+                log(&|| Step::Compare(Item::Text(left.list()), Item::Text(r)));
+                log(&|| Step::Convert(Side::Right, r));
+                log(&|| Step::Compare(Item::Text(left.list()), Item::Promoted(r)));
+                right.promote(r);
+            }
+            // the list that runs out of items first is smaller
+            (Some(Token::Close) | None, _) => {
+                log(&|| Step::RanOut(Side::Left));
+                return Ordering::Less;
+            }
+            (_, Some(Token::Close) | None) => {
+                log(&|| Step::RanOut(Side::Right));
+                return Ordering::Greater;
+            }
+        }
+    }
+}
+
+impl Ord for Packet<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(*self, *other, None)
+    }
+}
+
+impl PartialOrd for Packet<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Equal by the comparison rules, so `[1]` equals `[[1]]`
+impl PartialEq for Packet<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Packet<'_> {}
+
+/// What the packet parser expects next
+#[derive(Clone, Copy)]
+enum Expect {
+    Packet,
+    Item,
+    ItemOrClose,
+    CommaOrClose,
+    End,
+}
+
+impl Expect {
+    fn description(self) -> &'static str {
+        match self {
+            Expect::Packet => "a packet (a list like [1,[2,3]])",
+            Expect::Item => "an integer or a list",
+            Expect::ItemOrClose => "an integer, a list or ']'",
+            Expect::CommaOrClose => "',' or ']'",
+            Expect::End => "the end of the packet",
         }
     }
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
+/// Parse a packet line like `[1,[2,3]]`: a list of integers and lists, separated by commas
+fn parse_packet<'a>(input: &str, line: &'a str) -> Result<Packet<'a>, ParseError> {
+    let mut expect = Expect::Packet;
+    let mut depth: usize = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        expect = match (expect, c) {
+            (Expect::Packet | Expect::Item | Expect::ItemOrClose, '[') => {
+                depth += 1;
+                Expect::ItemOrClose
+            }
+            (Expect::Item | Expect::ItemOrClose, '0'..='9') => {
+                while chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {}
+                Expect::CommaOrClose
+            }
+            (Expect::ItemOrClose | Expect::CommaOrClose, ']') => {
+                depth -= 1;
+                match depth {
+                    0 => Expect::End,
+                    _ => Expect::CommaOrClose,
+                }
+            }
+            (Expect::CommaOrClose, ',') => Expect::Item,
+            (expect, _) => {
+                return Err(ParseError::at(
+                    input,
+                    &line[position..],
+                    expect.description(),
+                ))
+            }
+        };
+    }
+    match expect {
+        Expect::End => Ok(Packet(line)),
+        expect => Err(ParseError::at(
+            input,
+            &line[line.len()..],
+            expect.description(),
+        )),
+    }
+}
+
+fn parse_packets(input: &Input) -> Result<Vec<[Packet<'_>; 2]>, ParseError> {
+    let mut packet_pairs: Vec<[Packet; 2]> = vec![];
+    for raw_packet_pair in input.blocks() {
+        let raw_packets: Vec<&str> = raw_packet_pair.lines().collect();
+        let [raw_packet0, raw_packet1] = raw_packets[..] else {
+            let position = raw_packets.get(2).copied().unwrap_or(raw_packet_pair);
+            return Err(ParseError::at(input, position, "a pair of packets"));
+        };
+        packet_pairs.push([
+            parse_packet(input, raw_packet0)?,
+            parse_packet(input, raw_packet1)?,
+        ]);
+    }
+    Ok(packet_pairs)
+}
+
+pub fn part_one(input: &Input) -> Result<usize, ParseError> {
     let packet_pairs = parse_packets(input)?;
 
     let mut pairs_in_correct_order: Vec<usize> = vec![];
     for (i, packet_pair) in packet_pairs.iter().enumerate() {
         let [left_packet, right_packet] = packet_pair;
-        if validate(left_packet, right_packet) {
+        if left_packet < right_packet {
             pairs_in_correct_order.push(i + 1);
         }
     }
 
-    Some(pairs_in_correct_order.iter().sum())
+    Ok(pairs_in_correct_order.iter().sum())
+}
+
+pub fn part_two(input: &Input) -> Result<usize, ParseError> {
+    let dividers = [Packet("[[2]]"), Packet("[[6]]")];
+
+    // the dividers go first, so the stable sort keeps them before the packets that compare
+    // equal to them (like `[2]`)
+    let mut packets: Vec<Packet> = dividers.to_vec();
+    packets.extend(parse_packets(input)?.into_iter().flatten());
+    packets.sort();
+
+    // dividers are found by address, as a packet in the input can have the same text
+    let decoder_key = dividers
+        .iter()
+        .map(|divider| {
+            let is_divider = |packet: &Packet| std::ptr::eq(packet.0, divider.0);
+            packets
+                .iter()
+                .position(is_divider)
+                .expect("dividers are sorted too")
+                + 1
+        })
+        .product();
+    Ok(decoder_key)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    None
+/// Both packets of the pair side by side, followed by the steps of comparing them
+fn explain_pair(number: usize, [left, right]: [Packet; 2]) -> String {
+    let mut explanation = format!("== Pair {number} ==\n");
+    let (left_pretty, right_pretty) = (format!("{left:#}"), format!("{right:#}"));
+    let width = left_pretty.lines().map(str::len).max().unwrap_or(0);
+    let mut right_lines = right_pretty.lines();
+    for left_line in left_pretty.lines() {
+        let right_line = right_lines.next().unwrap_or_default();
+        let row = format!("{left_line:width$}    {right_line}");
+        writeln!(explanation, "{}", row.trim_end()).unwrap();
+    }
+    for right_line in right_lines {
+        writeln!(explanation, "{:width$}    {right_line}", "").unwrap();
+    }
+    explanation.push('\n');
+
+    // how much deeper the items of every list being compared are, which is one more for a
+    // list that an integer was converted to
+    let (mut depth, mut levels, mut converted) = (0, vec![], 0);
+    let order = |side| match side {
+        Side::Left => "are in the right order",
+        Side::Right => "are not in the right order",
+    };
+    let mut write_step = |step| {
+        let indent = "  ".repeat(depth);
+        match step {
+            Step::Compare(left, right) => {
+                writeln!(explanation, "{indent}- Compare {left} vs {right}").unwrap();
+                let is_list = |item| !matches!(item, Item::Text(text) if !text.starts_with('['));
+                if is_list(left) && is_list(right) {
+                    depth += 1;
+                    levels.push(1 + converted);
+                    converted = 0;
+                }
+            }
+            Step::Convert(side, integer) => {
+                let side = if side == Side::Left { "left" } else { "right" };
+                writeln!(
+                    explanation,
+                    "{indent}  - Mixed types; convert {side} to [{integer}] and retry comparison"
+                )
+                .unwrap();
+                depth += 1;
+                converted += 1;
+            }
+            Step::EndOfLists => depth -= levels.pop().unwrap_or(0),
+            Step::Smaller(side) => {
+                let smaller = if side == Side::Left { "Left" } else { "Right" };
+                let order = order(side);
+                writeln!(
+                    explanation,
+                    "{indent}  - {smaller} side is smaller, so inputs {order}"
+                )
+                .unwrap();
+            }
+            Step::RanOut(side) => {
+                let ran_out = if side == Side::Left { "Left" } else { "Right" };
+                let order = order(side);
+                writeln!(
+                    explanation,
+                    "{indent}- {ran_out} side ran out of items, so inputs {order}"
+                )
+                .unwrap();
+            }
+        }
+    };
+    compare(left, right, Some(&mut write_step));
+    explanation
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 13);
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--trace") {
+        // `cargo solve 13 -- --trace [--pair N]` explains how the pairs are compared
+        let pair: Option<usize> = args.opt_value_from_str("--pair").unwrap_or_else(|e| {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        });
+        let packet_pairs = parse_packets(input).unwrap_or_else(|e| {
+            eprintln!("error: {e}");
+            process::exit(1);
+        });
+        for (i, packet_pair) in packet_pairs.into_iter().enumerate() {
+            if pair.is_none_or(|pair| pair == i + 1) {
+                println!("{}", explain_pair(i + 1, packet_pair));
+            }
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -94,16 +491,300 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, generate::generate, prop, random::Rng};
+    use std::{hint::black_box, time::Instant};
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 13);
-        assert_eq!(part_one(&input), Some(13));
+        assert_eq!(part_one(&input), Ok(13));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 13);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Ok(140));
+
+        // packets equal to a divider go after it: [1] [[2]] [2] [[6]] [[6]] [7]
+        let input = Input::new("[2]\n[[6]]\n\n[1]\n[7]\n");
+        assert_eq!(part_two(&input), Ok(2 * 4));
+        // even when they have the same text: [[2]] [[2]] [[[2]]] [[2]] [[6]] [6]
+        let input = Input::new("[[2]]\n[6]\n\n[[[2]]]\n[[2]]\n");
+        assert_eq!(part_two(&input), Ok(5));
+    }
+
+    #[test]
+    fn test_ordering() {
+        let input = advent_of_code::read_file("examples", 13);
+        let orderings: Vec<Ordering> = parse_packets(&input)
+            .unwrap()
+            .iter()
+            .map(|[left, right]| left.cmp(right))
+            .collect();
+        use Ordering::*;
+        assert_eq!(
+            orderings,
+            vec![Less, Less, Greater, Less, Greater, Less, Greater, Greater]
+        );
+        assert_eq!(Packet("[1]"), Packet("[[1]]"));
+    }
+
+    #[test]
+    fn test_comparison_rules() {
+        use Ordering::*;
+        for (left, right, ordering) in [
+            // integers compare by value
+            ("[1]", "[2]", Less),
+            ("[3]", "[3]", Equal),
+            ("[10]", "[9]", Greater),
+            // equal items don't decide, the next ones do
+            ("[1,1,3,1,1]", "[1,1,5,1,1]", Less),
+            ("[2,2,4]", "[2,2,3]", Greater),
+            // the list that runs out of items first is smaller
+            ("[7,7,7]", "[7,7,7,7]", Less),
+            ("[7,7,7,7]", "[7,7,7]", Greater),
+            ("[]", "[3]", Less),
+            ("[[]]", "[]", Greater),
+            ("[[[]]]", "[[]]", Greater),
+            // an integer compared with a list is a list of that integer
+            ("[[1],[2,3,4]]", "[[1],4]", Less),
+            ("[9]", "[[8,7,6]]", Greater),
+            ("[[4,4],4,4]", "[[4,4],4,4,4]", Less),
+            ("[2]", "[[2]]", Equal),
+            ("[[2]]", "[2,1]", Less),
+            // integers can be of any size, and have leading zeros
+            ("[18446744073709551616]", "[18446744073709551615]", Greater),
+            ("[007,[3]]", "[7,3]", Equal),
+            (
+                "[1,[2,[3,[4,[5,6,7]]]],8,9]",
+                "[1,[2,[3,[4,[5,6,0]]]],8,9]",
+                Greater,
+            ),
+        ] {
+            let (left, right) = (parse_packet(left, left), parse_packet(right, right));
+            let (left, right) = (left.unwrap(), right.unwrap());
+            assert_eq!(left.cmp(&right), ordering, "{left:?} vs {right:?}");
+            assert_eq!(
+                right.cmp(&left),
+                ordering.reverse(),
+                "{right:?} vs {left:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_explain_pair() {
+        let input = advent_of_code::read_file("examples", 13);
+        let packet_pairs = parse_packets(&input).unwrap();
+        assert_eq!(
+            explain_pair(2, packet_pairs[1]),
+            "== Pair 2 ==\n\
+             [            [\n  [1],         [1],\n  [2,3,4]      4\n]            ]\n\n\
+             - Compare [[1],[2,3,4]] vs [[1],4]\n  \
+               - Compare [1] vs [1]\n    \
+                 - Compare 1 vs 1\n  \
+               - Compare [2,3,4] vs 4\n    \
+                 - Mixed types; convert right to [4] and retry comparison\n    \
+                 - Compare [2,3,4] vs [4]\n      \
+                   - Compare 2 vs 4\n        \
+                     - Left side is smaller, so inputs are in the right order\n"
+        );
+        assert!(explain_pair(5, packet_pairs[4])
+            .ends_with("  - Right side ran out of items, so inputs are not in the right order\n"));
+        assert_eq!(
+            format!("{:#}", packet_pairs[3][1]),
+            "[\n  [4,4],\n  4,\n  4,\n  4\n]"
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        for (input, position) in [
+            ("[1,2]\n[1,a]", (2, 4)),
+            ("[1,2]\n[1,[2]", (2, 7)),
+            ("[1,2]\n[1,]", (2, 4)),
+            ("[1,2]\n[1]]", (2, 4)),
+            ("[1,2]\n1", (2, 1)),
+            ("[1,2]\n[1]\n[2]", (3, 1)),
+        ] {
+            let error = part_one(&Input::new(input)).unwrap_err();
+            assert_eq!((error.line, error.column), position, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        fuzz(13, |input| part_one(input).and(part_two(input)));
+    }
+
+    /// The packets as they were parsed with serde before, kept to benchmark against
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum SerdePacket {
+        Value(u8),
+        List(Vec<SerdePacket>),
+    }
+
+    fn serde_compare(left: &SerdePacket, right: &SerdePacket) -> Ordering {
+        match (left, right) {
+            (SerdePacket::Value(l), SerdePacket::Value(r)) => l.cmp(r),
+            (SerdePacket::Value(l), SerdePacket::List(_)) => {
+                serde_compare(&SerdePacket::List(vec![SerdePacket::Value(*l)]), right)
+            }
+            (SerdePacket::List(_), SerdePacket::Value(r)) => {
+                serde_compare(left, &SerdePacket::List(vec![SerdePacket::Value(*r)]))
+            }
+            (SerdePacket::List(l), SerdePacket::List(r)) => l
+                .iter()
+                .zip(r)
+                .map(|(l, r)| serde_compare(l, r))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| l.len().cmp(&r.len())),
+        }
+    }
+
+    /// Part one as it was solved with the serde packets
+    fn serde_part_one(input: &Input) -> usize {
+        input
+            .blocks()
+            .enumerate()
+            .filter(|(_, pair)| {
+                let packets: Vec<SerdePacket> = pair
+                    .lines()
+                    .map(|line| serde_json::from_str(line).unwrap())
+                    .collect();
+                serde_compare(&packets[0], &packets[1]) == Ordering::Less
+            })
+            .map(|(i, _)| i + 1)
+            .sum()
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with `cargo test --release --bin 13 bench_parsers -- --ignored --nocapture`"]
+    fn bench_parsers() {
+        let input = Input::new(&generate(13, 20000, 1).unwrap());
+        let expected = part_one(&input).unwrap();
+        // both parse and compare every pair, like part one
+        let bench = |name: &str, solve: &dyn Fn(&Input) -> usize| {
+            let fastest = (0..10)
+                .map(|_| {
+                    let timer = Instant::now();
+                    assert_eq!(black_box(solve(black_box(&input))), expected);
+                    timer.elapsed()
+                })
+                .min()
+                .unwrap();
+            println!("{name}: {fastest:.2?} for {} pairs", input.blocks().count());
+        };
+        bench("hand-written", &|input| part_one(input).unwrap());
+        bench("serde", &serde_part_one);
+    }
+
+    #[derive(Debug, Clone)]
+    enum RandomPacket {
+        Integer(u8),
+        List(Vec<RandomPacket>),
+    }
+
+    impl fmt::Display for RandomPacket {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Integer(value) => write!(f, "{value}"),
+                Self::List(items) => {
+                    let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                    write!(f, "[{}]", items.join(","))
+                }
+            }
+        }
+    }
+
+    fn random_list(rng: &mut Rng, depth: usize) -> Vec<RandomPacket> {
+        (0..rng.range(0..5))
+            .map(|_| match depth < 3 && rng.chance(1, 3) {
+                true => RandomPacket::List(random_list(rng, depth + 1)),
+                false => RandomPacket::Integer(rng.range(0..11) as u8),
+            })
+            .collect()
+    }
+
+    /// Copy of the packet with a small random change, so pairs often share a prefix
+    fn mutate(rng: &mut Rng, packet: &[RandomPacket]) -> Vec<RandomPacket> {
+        let mut packet = packet.to_vec();
+        if packet.is_empty() || rng.chance(1, 4) {
+            packet.push(RandomPacket::Integer(rng.range(0..11) as u8));
+            return packet;
+        }
+        let i = rng.range(0..packet.len());
+        packet[i] = match &packet[i] {
+            RandomPacket::List(items) if rng.chance(1, 2) => RandomPacket::List(mutate(rng, items)),
+            RandomPacket::Integer(value) if rng.chance(1, 2) => {
+                RandomPacket::List(vec![RandomPacket::Integer(*value)])
+            }
+            _ => RandomPacket::Integer(rng.range(0..11) as u8),
+        };
+        packet
+    }
+
+    fn reference_compare(left: &RandomPacket, right: &RandomPacket) -> Ordering {
+        match (left, right) {
+            (RandomPacket::Integer(l), RandomPacket::Integer(r)) => l.cmp(r),
+            (RandomPacket::Integer(_), RandomPacket::List(_)) => {
+                reference_compare(&RandomPacket::List(vec![left.clone()]), right)
+            }
+            (RandomPacket::List(_), RandomPacket::Integer(_)) => {
+                reference_compare(left, &RandomPacket::List(vec![right.clone()]))
+            }
+            (RandomPacket::List(l), RandomPacket::List(r)) => {
+                for (l, r) in l.iter().zip(r) {
+                    match reference_compare(l, r) {
+                        Ordering::Equal => continue,
+                        ordering => return ordering,
+                    }
+                }
+                l.len().cmp(&r.len())
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(
+            |rng| {
+                (0..rng.range(1..10))
+                    .map(|_| {
+                        let left = random_list(rng, 0);
+                        let right = mutate(rng, &left);
+                        match rng.chance(1, 2) {
+                            true => [RandomPacket::List(left), RandomPacket::List(right)],
+                            false => [RandomPacket::List(right), RandomPacket::List(left)],
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            },
+            |pairs| {
+                let raw_pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|[left, right]| format!("{left}\n{right}\n"))
+                    .collect();
+                let input = Input::new(&raw_pairs.join("\n"));
+
+                let in_order = pairs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, [left, right])| reference_compare(left, right) == Ordering::Less);
+                assert_eq!(part_one(&input), Ok(in_order.map(|(i, _)| i + 1).sum()));
+
+                // the explanation has to come to the same conclusion
+                for [left, right] in pairs {
+                    let (raw_left, raw_right) = (left.to_string(), right.to_string());
+                    let explanation = explain_pair(1, [Packet(&raw_left), Packet(&raw_right)]);
+                    assert_eq!(
+                        explanation.ends_with(", so inputs are in the right order\n"),
+                        reference_compare(left, right) == Ordering::Less,
+                        "{explanation}"
+                    );
+                }
+            },
+        );
     }
 }
@@ -1,4 +1,16 @@
-use advent_of_code::grid::{Grid, GridCoord};
+use advent_of_code::{
+    error::ParseError,
+    grid::{Grid, GridCoord},
+    helpers::{arrow_path, lines_of, parse_all, parse_coord},
+    time_phase, Input,
+};
+use nom::combinator::consumed;
+use std::{fmt, process};
+
+/// Size of the cave grid. Sand spreads at most one column per row it falls, so rocks must stay
+/// far enough above the bottom for the floor of part two to fit
+const CAVE_SIZE: usize = 1000;
+const MAX_ROCK_DEPTH: usize = CAVE_SIZE / 2 - 3;
 
 #[derive(Default, Clone, PartialEq, Eq)]
 enum Tile {
@@ -37,7 +49,7 @@ impl Cave {
             .iter_all_cells()
             .filter_map(|(coord, _)| {
                 if coord.y == bottom_row {
-                    Some(coord.clone())
+                    Some(coord)
                 } else {
                     None
                 }
@@ -50,6 +62,22 @@ impl Cave {
         Self { tiles, bottom_row }
     }
 
+    /// Whether the source of the sand is blocked, so no more sand can be released
+    fn is_source_blocked(&self) -> bool {
+        self.tiles.get_cell(&GridCoord::from((500, 0))) != Some(&Tile::Air)
+    }
+
+    /// Save a snapshot of the cave as an image, for debugging the sand simulation
+    fn save_image(&self, path: &str) -> std::io::Result<()> {
+        self.tiles
+            .to_image(|tile| match tile {
+                Tile::Air => [0, 0, 0],
+                Tile::Rock => [128, 128, 128],
+                Tile::Sand => [237, 201, 175],
+            })
+            .save(path)
+    }
+
     fn release_sand(&mut self) -> bool {
         let mut pos = GridCoord::from((500, 0));
         loop {
@@ -142,54 +170,93 @@ impl Cave {
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut cave = Cave::new(parse_tiles(input));
+impl fmt::Display for Cave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rendered = self.tiles.render_cropped(|tile| match tile {
+            Tile::Air => '.',
+            Tile::Rock => '#',
+            Tile::Sand => 'o',
+        });
+        write!(f, "{rendered}")
+    }
+}
+
+pub fn part_one(input: &Input) -> Result<u32, ParseError> {
+    let tiles = time_phase!("parse", parse_tiles(input)?);
+    let mut cave = time_phase!("find bottom row", Cave::new(tiles));
     let mut num_sand: u32 = 0;
 
-    loop {
-        let is_falling_into_abyss = cave.release_sand();
-        if is_falling_into_abyss {
-            break;
+    time_phase!(
+        "release sand",
+        while !cave.is_source_blocked() {
+            let is_falling_into_abyss = cave.release_sand();
+            if is_falling_into_abyss {
+                break;
+            }
+            num_sand += 1;
         }
-        num_sand += 1;
-    }
+    );
 
-    Some(num_sand)
+    Ok(num_sand)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut cave = Cave::new_part_2(parse_tiles(input));
+pub fn part_two(input: &Input) -> Result<u32, ParseError> {
+    let (_, num_sand) = fill_cave(input)?;
+    Ok(num_sand)
+}
+
+/// The cave of part two once the source is blocked, and the units of sand that came to rest
+fn fill_cave(input: &str) -> Result<(Cave, u32), ParseError> {
+    let tiles = time_phase!("parse", parse_tiles(input)?);
+    let mut cave = time_phase!("add floor", Cave::new_part_2(tiles));
     let mut num_sand: u32 = 0;
 
-    loop {
-        num_sand += 1;
-        let has_reached_top = cave.release_sand_part_2();
-        if has_reached_top {
-            break;
+    time_phase!(
+        "release sand",
+        loop {
+            num_sand += 1;
+            let has_reached_top = cave.release_sand_part_2();
+            if has_reached_top {
+                break;
+            }
         }
-    }
+    );
 
-    Some(num_sand)
+    Ok((cave, num_sand))
 }
 
-fn parse_tiles(input: &str) -> Grid<Tile> {
-    let mut grid: Grid<Tile> = Grid::new(1000, 1000);
-    for path in input.lines() {
-        let raw_points = path.split(" -> ").collect::<Vec<&str>>();
-        for line in raw_points.windows(2) {
-            let raw_point_a = line.get(0).unwrap().split_once(",").unwrap();
-            let raw_point_b = line.get(1).unwrap().split_once(",").unwrap();
-            let point_a = GridCoord {
-                x: raw_point_a.0.parse().unwrap(),
-                y: raw_point_a.1.parse().unwrap(),
-            };
-            let point_b = GridCoord {
-                x: raw_point_b.0.parse().unwrap(),
-                y: raw_point_b.1.parse().unwrap(),
-            };
+fn parse_tiles(input: &str) -> Result<Grid<Tile>, ParseError> {
+    let mut grid: Grid<Tile> = time_phase!("allocate grid", Grid::new(CAVE_SIZE, CAVE_SIZE));
+    let paths = time_phase!(
+        "parse paths",
+        parse_all(input, lines_of(arrow_path(consumed(parse_coord::<usize>))))?
+    );
+    for path in paths {
+        for (raw_point, (x, y)) in &path {
+            if *x >= CAVE_SIZE || *y > MAX_ROCK_DEPTH {
+                let expected =
+                    format!("a point with x below {CAVE_SIZE} and y up to {MAX_ROCK_DEPTH}");
+                return Err(ParseError::at(input, raw_point, expected));
+            }
+        }
+        let points: Vec<GridCoord> = path
+            .iter()
+            .map(|(_, point)| GridCoord::from(*point))
+            .collect();
+        // a path can be a single point
+        *grid.get_cell_mut(&points[0]).expect("checked above") = Tile::Rock;
+        for (i, line) in points.windows(2).enumerate() {
+            let (point_a, point_b) = (line[0], line[1]);
+            if point_a.x != point_b.x && point_a.y != point_b.y {
+                return Err(ParseError::at(
+                    input,
+                    path[i + 1].0,
+                    "a point in line with the previous one (horizontal or vertical)",
+                ));
+            }
             let dx: i16 = i16::try_from(point_b.x).unwrap() - i16::try_from(point_a.x).unwrap();
             let dy: i16 = i16::try_from(point_b.y).unwrap() - i16::try_from(point_a.y).unwrap();
-            let mut current_point = point_a.clone();
+            let mut current_point = point_a;
             loop {
                 *grid
                     .get_cell_mut(&current_point)
@@ -219,11 +286,26 @@ fn parse_tiles(input: &str) -> Grid<Tile> {
         }
     }
 
-    grid
+    Ok(grid)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 14);
+    let mut args = pico_args::Arguments::from_env();
+    // `cargo solve 14 -- --image cave.png` saves the cave full of sand of part two (.png or .ppm)
+    let image: Option<String> = args.opt_value_from_str("--image").unwrap_or_else(|e| {
+        eprintln!("Failed to process arguments: {e}");
+        process::exit(1);
+    });
+    if let Some(path) = image {
+        match fill_cave(input) {
+            Ok((cave, _)) => match cave.save_image(&path) {
+                Ok(()) => println!("saved the cave to {path}"),
+                Err(e) => eprintln!("error: couldn't save {path}: {e}"),
+            },
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -231,16 +313,122 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::{fuzz::fuzz, prop, random::Rng};
+    use std::collections::HashSet;
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 14);
-        assert_eq!(part_one(&input), Some(24));
+        assert_eq!(part_one(&input), Ok(24));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 14);
-        assert_eq!(part_two(&input), Some(93));
+        assert_eq!(part_two(&input), Ok(93));
+    }
+
+    #[test]
+    fn test_render_cave() {
+        let input = advent_of_code::read_file("examples", 14);
+        let cave = Cave::new(parse_tiles(&input).unwrap());
+        assert_eq!(
+            cave.to_string(),
+            "....#...##\n....#...#.\n..###...#.\n........#.\n........#.\n#########.\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        for (input, position) in [
+            ("498,4 -> 498,6\n1000,4 -> 1000,6", (2, 1)),
+            ("498,4 -> 498,498", (1, 10)),
+            ("498,4 -> 498,6 -> 496,8", (1, 19)),
+        ] {
+            let error = part_one(&Input::new(input)).unwrap_err();
+            assert_eq!((error.line, error.column), position, "{input:?}");
+        }
+        assert_eq!(part_one(&Input::new("500,0 -> 500,2")), Ok(0));
+    }
+
+    #[test]
+    fn fuzz_parser() {
+        // the floor of part two takes a lot of sand to fill, which is too slow for many runs
+        fuzz(14, part_one);
+    }
+
+    /// Random rock paths below the sand source, alternating horizontal and vertical lines
+    fn random_paths(rng: &mut Rng) -> Vec<Vec<(usize, usize)>> {
+        (0..rng.range(1..6))
+            .map(|_| {
+                let mut point = (rng.range(488..513), rng.range(2..16));
+                let mut path = vec![point];
+                for i in 0..rng.range(1..4) {
+                    let length = rng.range_i64(-5..6);
+                    point = match i % 2 {
+                        0 => ((point.0 as i64 + length) as usize, point.1),
+                        _ => (point.0, (point.1 as i64 + length).clamp(1, 20) as usize),
+                    };
+                    path.push(point);
+                }
+                path
+            })
+            .collect()
+    }
+
+    /// Drop sand one unit at a time on a set of blocked points. Without a floor, counts the units
+    /// that come to rest before one falls past the lowest rock. With a floor, counts the units
+    /// until the source is blocked.
+    fn reference_units_of_sand(paths: &[Vec<(usize, usize)>], has_floor: bool) -> u32 {
+        let mut blocked = HashSet::new();
+        for path in paths {
+            for line in path.windows(2) {
+                let ((x1, y1), (x2, y2)) = (line[0], line[1]);
+                for x in x1.min(x2)..=x1.max(x2) {
+                    for y in y1.min(y2)..=y1.max(y2) {
+                        blocked.insert((x, y));
+                    }
+                }
+            }
+        }
+        let lowest_rock = blocked.iter().map(|(_, y)| *y).max().unwrap();
+        let floor = lowest_rock + 2;
+
+        let mut units = 0;
+        while !blocked.contains(&(500, 0)) {
+            let (mut x, mut y) = (500, 0);
+            loop {
+                let next = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)]
+                    .into_iter()
+                    .find(|point| !(blocked.contains(point) || has_floor && point.1 == floor));
+                match next {
+                    Some(point) => (x, y) = point,
+                    None => break,
+                }
+                if !has_floor && y > lowest_rock {
+                    return units;
+                }
+            }
+            blocked.insert((x, y));
+            units += 1;
+        }
+        units
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        prop::check(random_paths, |paths| {
+            let raw_paths: Vec<String> = paths
+                .iter()
+                .map(|path| {
+                    let points: Vec<String> =
+                        path.iter().map(|(x, y)| format!("{x},{y}")).collect();
+                    points.join(" -> ")
+                })
+                .collect();
+            let input = Input::new(&raw_paths.join("\n"));
+            assert_eq!(part_one(&input), Ok(reference_units_of_sand(paths, false)));
+            assert_eq!(part_two(&input), Ok(reference_units_of_sand(paths, true)));
+        });
     }
 }
@@ -0,0 +1,40 @@
+/*
+ * Generate a large synthetic input for a day, see `advent_of_code::generate`.
+ * example: `cargo gen 14 --size 10000 --seed 3 > big.txt`
+ */
+use advent_of_code::generate::generate;
+use std::process;
+
+struct Args {
+    day: u8,
+    size: usize,
+    seed: u64,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        size: args.opt_value_from_str(["-n", "--size"])?.unwrap_or(1000),
+        seed: args.opt_value_from_str(["-s", "--seed"])?.unwrap_or(0),
+        day: args.free_from_str()?,
+    })
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            eprintln!("usage: `cargo gen <day> [--size N] [--seed S]`");
+            process::exit(1);
+        }
+    };
+
+    match generate(args.day, args.size, args.seed) {
+        Some(input) => print!("{input}"),
+        None => {
+            eprintln!("There is no input generator for day {}.", args.day);
+            process::exit(1);
+        }
+    }
+}
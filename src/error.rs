@@ -0,0 +1,203 @@
+use std::fmt::{self, Display};
+
+use nom::error::{ErrorKind, VerboseError, VerboseErrorKind};
+
+/// Error when parsing the puzzle input. Displays as a report pointing at the offending spot:
+///
+/// ```text
+/// expected a number at line 2, column 19 (in Starting items)
+///   2 |   Starting items: x9, 98
+///     |                   ^
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /** line number, starting at 1 */
+    pub line: usize,
+    /** column (in characters), starting at 1 */
+    pub column: usize,
+    pub line_text: String,
+    pub expected: String,
+    /** what was being parsed, from the innermost to the outermost context */
+    pub contexts: Vec<String>,
+}
+
+impl ParseError {
+    /// Error at the start of `position`, which must be a slice of `input`, or a suffix of it as
+    /// returned by nom parsers
+    pub fn at(input: &str, position: &str, expected: impl Into<String>) -> Self {
+        let offset = Self::offset(input, position);
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        let line_text = input[line_start..line_end].trim_end_matches('\r');
+        Self {
+            line: input[..line_start].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            line_text: line_text.to_string(),
+            expected: expected.into(),
+            contexts: vec![],
+        }
+    }
+
+    /// Convert the error of a nom parser, that was called on `input`
+    pub fn from_nom(input: &str, error: nom::Err<VerboseError<&str>>) -> Self {
+        let error = match error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => error,
+            nom::Err::Incomplete(_) => return Self::at(input, "", "more input"),
+        };
+        let Some((position, innermost_kind)) = error.errors.first() else {
+            return Self::at(input, input, "valid input");
+        };
+
+        // a context added at the spot where parsing failed describes what was expected there,
+        // other contexts describe what was being parsed around it
+        let mut expected = describe(innermost_kind);
+        let mut contexts: Vec<String> = vec![];
+        for (context_position, kind) in &error.errors {
+            if let VerboseErrorKind::Context(context) = kind {
+                if context_position.len() == position.len() && contexts.is_empty() {
+                    expected = context.to_string();
+                } else {
+                    contexts.push(context.to_string());
+                }
+            }
+        }
+
+        Self {
+            contexts,
+            ..Self::at(input, position, expected)
+        }
+    }
+
+    fn offset(input: &str, position: &str) -> usize {
+        let start = input.as_ptr() as usize;
+        let position_start = position.as_ptr() as usize;
+        if position_start >= start && position_start + position.len() <= start + input.len() {
+            position_start - start
+        } else {
+            input.len().saturating_sub(position.len())
+        }
+    }
+}
+
+fn describe(kind: &VerboseErrorKind) -> String {
+    match kind {
+        VerboseErrorKind::Context(context) => context.to_string(),
+        VerboseErrorKind::Char(c) => format!("'{}'", c.escape_default()),
+        VerboseErrorKind::Nom(kind) => match kind {
+            ErrorKind::Digit => "a number".to_string(),
+            ErrorKind::TooLarge => "a smaller number".to_string(),
+            ErrorKind::Eof => "end of input".to_string(),
+            ErrorKind::CrLf => "a line break".to_string(),
+            ErrorKind::Space | ErrorKind::MultiSpace => "whitespace".to_string(),
+            kind => format!("valid input ({})", kind.description()),
+        },
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {} at line {}, column {}",
+            self.expected, self.line, self.column
+        )?;
+        if !self.contexts.is_empty() {
+            write!(f, " (in {})", self.contexts.join(", in "))?;
+        }
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f)?;
+        writeln!(f, " {} | {}", self.line, self.line_text)?;
+        write!(f, " {gutter} | {}^", " ".repeat(self.column - 1))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error when solving a puzzle: the input doesn't parse, or it does but there is no answer for it
+/// (like a maze without a way out). Only parse errors point at a spot in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    Parse(ParseError),
+    /** the input parsed, but has no answer, for the given reason */
+    Unsolvable(String),
+}
+
+impl From<ParseError> for PuzzleError {
+    fn from(error: ParseError) -> Self {
+        PuzzleError::Parse(error)
+    }
+}
+
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::Parse(error) => write!(f, "{error}"),
+            PuzzleError::Unsolvable(reason) => write!(f, "no answer, as {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{comma_list, key_value, parse_num};
+    use nom::{bytes::complete::tag, character::complete::line_ending, sequence::preceded};
+
+    #[test]
+    fn test_manual_error() {
+        let input = "30373\n25512\n65x32\n";
+        let line = input.lines().nth(2).unwrap();
+        let error = ParseError::at(input, &line[2..], "a digit");
+        assert_eq!((error.line, error.column), (3, 3));
+        assert_eq!(error.line_text, "65x32");
+        assert_eq!(
+            error.to_string(),
+            "expected a digit at line 3, column 3\n 3 | 65x32\n   |   ^"
+        );
+    }
+
+    #[test]
+    fn test_nom_error() {
+        let input = "Monkey 0:\r\n  Starting items: 79, x8\r\n";
+        let result = preceded(
+            preceded(tag("Monkey 0:"), line_ending),
+            key_value("Starting items", comma_list(parse_num::<u64>)),
+        )(input);
+        let error = ParseError::from_nom(input, result.unwrap_err());
+        assert_eq!(error.expected, "a number");
+        assert_eq!(error.contexts, vec!["Starting items".to_string()]);
+        assert_eq!(
+            error.to_string(),
+            "expected a number at line 2, column 23 (in Starting items)\n \
+             2 |   Starting items: 79, x8\n   \
+               |                       ^"
+        );
+
+        // a context at the failing spot is used as the expected token
+        let input = "Monkey 0:\n  Starting itens: 79";
+        let result = preceded(
+            preceded(tag("Monkey 0:"), line_ending),
+            key_value("Starting items", comma_list(parse_num::<u64>)),
+        )(input);
+        let error = ParseError::from_nom(input, result.unwrap_err());
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(error.expected, "Starting items");
+        assert!(error.contexts.is_empty());
+    }
+
+    #[test]
+    fn test_puzzle_error() {
+        let input = "Sab\nabc\n";
+        let error = PuzzleError::from(ParseError::at(input, &input[1..], "S or E"));
+        assert_eq!(
+            error.to_string(),
+            "expected S or E at line 1, column 2\n 1 | Sab\n   |  ^"
+        );
+        let error = PuzzleError::Unsolvable("there's no end square (E)".to_string());
+        assert_eq!(error.to_string(), "no answer, as there's no end square (E)");
+    }
+}
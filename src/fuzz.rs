@@ -0,0 +1,162 @@
+//! Mutation-based fuzzing of the puzzle parsers, in the spirit of cargo-fuzz but without the
+//! nightly toolchain or any network access. Every day has a `fuzz_parser` test that runs its
+//! parser (and its solvers, where they're fast enough) with `fuzz`, which first runs the corpus
+//! of the day as is, and then random mutations of it. The corpus is the example of the day
+//! (`src/examples`) plus the files in `fuzz/corpus/<day>/`, where inputs that once crashed a
+//! parser are kept as regression tests.
+//!
+//! A short run is part of the normal tests. For a longer run, set the number of mutated inputs
+//! (and optionally the seed) with environment variables:
+//!
+//! ```text
+//! FUZZ_ITERATIONS=1000000 FUZZ_SEED=42 cargo test --bin 06 fuzz_parser
+//! ```
+//!
+//! Debug builds are preferable to release builds here, as they also catch integer overflows. An
+//! input that makes the parser panic is saved to `fuzz/artifacts/<day>/`.
+
+use crate::{prop::env_u64, random::Rng, Input};
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+/// Number of mutated inputs per run, if `FUZZ_ITERATIONS` isn't set
+pub const DEFAULT_ITERATIONS: u64 = 300;
+
+/// Characters that are inserted by mutations: the ones the parsers care about, plus a few that
+/// should never show up in puzzle input
+const INTERESTING_CHARS: &[char] = &[
+    '0', '1', '9', ' ', '\n', '\r', '\t', ',', '-', '+', ':', '[', ']', '>', 'a', 'z', 'A', 'Z',
+    'S', 'E', '$', '/', '.', 'é', '\u{0}',
+];
+
+/// Numbers that are swapped in for the numbers in the input
+const INTERESTING_NUMBERS: &[&str] = &[
+    "0",
+    "-1",
+    "255",
+    "256",
+    "4294967296",
+    "18446744073709551616",
+    "99999999999999999999999999",
+];
+
+/// Run the target on the corpus of the day and on random mutations of it. Panics (after saving
+/// the input) if the target panics on any of them.
+pub fn fuzz<T>(day: u8, target: impl Fn(&Input) -> T) {
+    let corpus = load_corpus(day);
+    let iterations = env_u64("FUZZ_ITERATIONS").unwrap_or(DEFAULT_ITERATIONS);
+    let mut rng = Rng::new(env_u64("FUZZ_SEED").unwrap_or(0));
+
+    for entry in &corpus {
+        run_target(day, entry, &target);
+    }
+    for _ in 0..iterations {
+        let mut chars: Vec<char> = rng.choose(&corpus).chars().collect();
+        for _ in 0..rng.range(1..5) {
+            mutate(&mut rng, &mut chars);
+        }
+        run_target(day, &chars.into_iter().collect::<String>(), &target);
+    }
+}
+
+fn run_target<T>(day: u8, raw_input: &str, target: &impl Fn(&Input) -> T) {
+    let input = Input::new(raw_input);
+    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| target(&input))) {
+        let path = save_artifact(day, raw_input);
+        eprintln!("the parser of day {day:02} crashed on this input (saved to {path:?}):");
+        eprintln!("{raw_input:?}");
+        panic::resume_unwind(panic);
+    }
+}
+
+fn mutate(rng: &mut Rng, chars: &mut Vec<char>) {
+    let position = rng.range(0..chars.len() + 1);
+    let len = rng.range(1..8).min(chars.len() - position);
+    match rng.range(0..7) {
+        0 if position < chars.len() => chars[position] = *rng.choose(INTERESTING_CHARS),
+        1 => chars.insert(position, *rng.choose(INTERESTING_CHARS)),
+        2 => {
+            chars.drain(position..position + len);
+        }
+        3 => {
+            let copy: Vec<char> = chars[position..position + len].to_vec();
+            chars.splice(position..position, copy);
+        }
+        4 => chars.truncate(position),
+        5 => {
+            // replace the number around the position
+            let start = chars[..position]
+                .iter()
+                .rposition(|c| !c.is_ascii_digit())
+                .map_or(0, |i| i + 1);
+            let end = chars[position..]
+                .iter()
+                .position(|c| !c.is_ascii_digit())
+                .map_or(chars.len(), |i| position + i);
+            chars.splice(start..end, rng.choose(INTERESTING_NUMBERS).chars());
+        }
+        _ => {
+            // swap two lines
+            let text: String = chars.iter().collect();
+            let mut lines: Vec<&str> = text.split('\n').collect();
+            let (a, b) = (rng.range(0..lines.len()), rng.range(0..lines.len()));
+            lines.swap(a, b);
+            *chars = lines.join("\n").chars().collect();
+        }
+    }
+}
+
+fn load_corpus(day: u8) -> Vec<String> {
+    let cwd = env::current_dir().unwrap();
+    let example = cwd.join("src/examples").join(format!("{day:02}.txt"));
+    let mut corpus = vec![fs::read_to_string(example).expect("could not open example file")];
+
+    let corpus_dir = cwd.join("fuzz/corpus").join(format!("{day:02}"));
+    if let Ok(entries) = fs::read_dir(corpus_dir) {
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+        paths.sort();
+        for path in paths {
+            let bytes = fs::read(&path).expect("could not read corpus file");
+            corpus.push(String::from_utf8_lossy(&bytes).into_owned());
+        }
+    }
+    corpus
+}
+
+fn save_artifact(day: u8, raw_input: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    raw_input.hash(&mut hasher);
+    let dir = Path::new("fuzz/artifacts").join(format!("{day:02}"));
+    let path = dir.join(format!("crash-{:016x}", hasher.finish()));
+    if let Err(e) = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, raw_input)) {
+        eprintln!("could not save the crashing input: {e}");
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mutations_stay_valid() {
+        let mut rng = Rng::new(3);
+        let mut chars: Vec<char> = "1000\n2000\n\n3000".chars().collect();
+        for _ in 0..1000 {
+            mutate(&mut rng, &mut chars);
+            if chars.len() > 100 {
+                chars.truncate(10);
+            }
+        }
+        // an empty input can always be mutated further
+        let mut empty = vec![];
+        for _ in 0..100 {
+            mutate(&mut rng, &mut empty);
+        }
+    }
+}
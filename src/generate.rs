@@ -0,0 +1,439 @@
+//! Generators for synthetic puzzle inputs that are much larger than the real ones, to benchmark
+//! and stress-test the solutions. Every input is valid for both parts of its day, and the same
+//! day, size and seed always produce the same input. Run them with `cargo gen`, e.g.
+//!
+//! ```text
+//! cargo gen 14 --size 10000 --seed 3 > big.txt
+//! ```
+
+use crate::random::Rng;
+use std::fmt::{self, Write};
+
+/// Generate an input for the day with about `size` items (elves, lines, monkeys, packet pairs,
+/// etc.), or `size` rows for the grids of days 08 and 12. Returns `None` for days without a
+/// generator.
+pub fn generate(day: u8, size: usize, seed: u64) -> Option<String> {
+    let rng = &mut Rng::new(seed);
+    let size = size.max(1);
+    let input = match day {
+        1 => calories(rng, size),
+        2 => strategy_guide(rng, size),
+        3 => rucksacks(rng, size),
+        4 => section_assignments(rng, size),
+        5 => crate_stacks(rng, size),
+        6 => datastream(rng, size),
+        7 => terminal_output(rng, size),
+        8 => tree_heights(rng, size),
+        9 => rope_motions(rng, size),
+        10 => cpu_program(rng, size),
+        11 => monkey_notes(rng, size),
+        12 => heightmap(rng, size),
+        13 => packet_pairs(rng, size),
+        14 => rock_paths(rng, size),
+        _ => return None,
+    };
+    Some(input)
+}
+
+/// Join lines with a newline after each of them
+fn lines(lines: impl IntoIterator<Item = String>) -> String {
+    lines.into_iter().fold(String::new(), |mut output, line| {
+        writeln!(output, "{line}").unwrap();
+        output
+    })
+}
+
+fn calories(rng: &mut Rng, num_elves: usize) -> String {
+    let elves: Vec<String> = (0..num_elves)
+        .map(|_| lines((0..rng.range(1..10)).map(|_| rng.range(1000..60000).to_string())))
+        .collect();
+    elves.join("\n")
+}
+
+fn strategy_guide(rng: &mut Rng, num_rounds: usize) -> String {
+    lines((0..num_rounds).map(|_| {
+        format!(
+            "{} {}",
+            rng.choose(&['A', 'B', 'C']),
+            rng.choose(&['X', 'Y', 'Z'])
+        )
+    }))
+}
+
+/// Groups of three rucksacks. Every rucksack has exactly one item type in both compartments, and
+/// every group exactly one item type (the badge) in all three rucksacks.
+fn rucksacks(rng: &mut Rng, num_rucksacks: usize) -> String {
+    let mut items: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+    let mut rucksacks = vec![];
+    for _ in 0..num_rucksacks.div_ceil(3) {
+        rng.shuffle(&mut items);
+        let badge = items[0];
+        // each rucksack of the group draws from its own items, so only the badge is shared
+        for pool in items[1..].chunks(17) {
+            let (left_pool, right_pool) = pool.split_at(8);
+            let len = rng.range(2..16);
+            let mut left: Vec<char> = (0..len).map(|_| *rng.choose(left_pool)).collect();
+            let mut right: Vec<char> = (0..len).map(|_| *rng.choose(right_pool)).collect();
+            // the item in both compartments is either the badge or one from the left pool
+            if rng.chance(1, 4) {
+                left[0] = badge;
+                right[0] = badge;
+            } else {
+                right[0] = left[0];
+                right[1] = badge;
+            }
+            rng.shuffle(&mut left);
+            rng.shuffle(&mut right);
+            rucksacks.push(left.into_iter().chain(right).collect());
+        }
+    }
+    lines(rucksacks)
+}
+
+fn section_assignments(rng: &mut Rng, num_pairs: usize) -> String {
+    let range = |rng: &mut Rng| {
+        let start = rng.range(1..100);
+        format!("{start}-{}", rng.range(start..100))
+    };
+    lines((0..num_pairs).map(|_| format!("{},{}", range(rng), range(rng))))
+}
+
+/// Nine stacks of crates, and `num_moves` moves that never take more crates than a stack has
+fn crate_stacks(rng: &mut Rng, num_moves: usize) -> String {
+    let max_height = (num_moves / 10).clamp(3, 50);
+    let mut stacks: Vec<Vec<char>> = (0..9)
+        .map(|_| {
+            (0..rng.range(1..max_height))
+                .map(|_| (b'A' + rng.range(0..26) as u8) as char)
+                .collect()
+        })
+        .collect();
+
+    let mut drawing = String::new();
+    for level in (0..stacks.iter().map(Vec::len).max().unwrap()).rev() {
+        let row: Vec<String> = stacks
+            .iter()
+            .map(|stack| {
+                stack
+                    .get(level)
+                    .map_or("   ".to_string(), |c| format!("[{c}]"))
+            })
+            .collect();
+        writeln!(drawing, "{}", row.join(" ")).unwrap();
+    }
+    let ids: Vec<String> = (1..=9).map(|id| format!(" {id} ")).collect();
+    writeln!(drawing, "{}", ids.join(" ")).unwrap();
+
+    let moves = (0..num_moves).map(|_| {
+        let non_empty: Vec<usize> = (0..9).filter(|&i| !stacks[i].is_empty()).collect();
+        let from = *rng.choose(&non_empty);
+        let to = (from + rng.range(1..9)) % 9;
+        let num_crates = rng.range(1..stacks[from].len().min(10) + 1);
+        let split = stacks[from].len() - num_crates;
+        let moved = stacks[from].split_off(split);
+        stacks[to].extend(moved);
+        format!("move {num_crates} from {} to {}", from + 1, to + 1)
+    });
+    format!("{drawing}\n{}", lines(moves))
+}
+
+/// Letters from a small alphabet, so that the start-of-message marker (14 different letters)
+/// only shows up near the end
+fn datastream(rng: &mut Rng, len: usize) -> String {
+    let len = len.max(14);
+    let mut stream: Vec<char> = (0..len - 14)
+        .map(|_| (b'a' + rng.range(0..8) as u8) as char)
+        .collect();
+    let mut marker: Vec<char> = ('a'..='z').collect();
+    rng.shuffle(&mut marker);
+    stream.extend(&marker[..14]);
+    let mut stream: String = stream.into_iter().collect();
+    stream.push('\n');
+    stream
+}
+
+/// Browse a random tree of `num_dirs` directories depth-first. The files add up to less than
+/// 70000000, the size of the disk, but one of them is large enough that space has to be freed.
+fn terminal_output(rng: &mut Rng, num_dirs: usize) -> String {
+    // every directory is a child of a directory created before it, which keeps the tree shallow
+    let mut children: Vec<Vec<usize>> = vec![vec![]; num_dirs];
+    for dir in 1..num_dirs {
+        children[rng.range(0..dir)].push(dir);
+    }
+    let max_file_size = (20_000_000 / (2 * num_dirs)).clamp(1, 300_000);
+    let mut space_left: usize = 24_000_000;
+    let large_file_dir = rng.range(0..num_dirs);
+    let mut output = String::from("$ cd /\n");
+    browse(
+        rng,
+        0,
+        &children,
+        max_file_size,
+        large_file_dir,
+        &mut space_left,
+        &mut output,
+    );
+    output
+}
+
+/// List the files and subdirectories of the directory, then browse the subdirectories
+fn browse(
+    rng: &mut Rng,
+    dir: usize,
+    children: &[Vec<usize>],
+    max_file_size: usize,
+    large_file_dir: usize,
+    space_left: &mut usize,
+    output: &mut String,
+) {
+    output.push_str("$ ls\n");
+    for child in &children[dir] {
+        writeln!(output, "dir d{child}").unwrap();
+    }
+    for i in 0..rng.range(0..5) {
+        let size = rng.range(1..max_file_size + 1);
+        if size <= *space_left {
+            *space_left -= size;
+            writeln!(output, "{size} f{i}.txt").unwrap();
+        }
+    }
+    if dir == large_file_dir {
+        writeln!(output, "{} large.bin", rng.range(40_000_000..45_000_000)).unwrap();
+    }
+    for &child in &children[dir] {
+        writeln!(output, "$ cd d{child}").unwrap();
+        browse(
+            rng,
+            child,
+            children,
+            max_file_size,
+            large_file_dir,
+            space_left,
+            output,
+        );
+        output.push_str("$ cd ..\n");
+    }
+}
+
+fn tree_heights(rng: &mut Rng, size: usize) -> String {
+    lines((0..size).map(|_| {
+        (0..size)
+            .map(|_| (b'0' + rng.range(0..10) as u8) as char)
+            .collect()
+    }))
+}
+
+fn rope_motions(rng: &mut Rng, num_motions: usize) -> String {
+    lines(
+        (0..num_motions)
+            .map(|_| format!("{} {}", rng.choose(&['U', 'D', 'L', 'R']), rng.range(1..20))),
+    )
+}
+
+/// A program of `num_instructions` instructions (at least enough to draw the whole screen), which
+/// keeps the register on the screen
+fn cpu_program(rng: &mut Rng, num_instructions: usize) -> String {
+    let mut register: i64 = 1;
+    let mut num_cycles = 0;
+    let mut program = vec![];
+    while program.len() < num_instructions || num_cycles <= 240 {
+        if rng.chance(1, 3) {
+            program.push("noop".to_string());
+            num_cycles += 1;
+        } else {
+            let value = rng.range_i64(-register..40 - register);
+            register += value;
+            program.push(format!("addx {value}"));
+            num_cycles += 2;
+        }
+    }
+    lines(program)
+}
+
+/// Notes on `num_monkeys` monkeys. Their divisors are small primes, so the worry levels of part
+/// two stay small, and they only multiply by 2 or 3, so the ones of part one do as well.
+fn monkey_notes(rng: &mut Rng, num_monkeys: usize) -> String {
+    let num_monkeys = num_monkeys.max(2);
+    let notes: Vec<String> = (0..num_monkeys)
+        .map(|id| {
+            let items: Vec<String> = (0..rng.range(1..6))
+                .map(|_| rng.range(50..100).to_string())
+                .collect();
+            let operation = match rng.chance(1, 2) {
+                true => format!("+ {}", rng.range(1..10)),
+                false => format!("* {}", rng.range(2..4)),
+            };
+            let mut other_monkey = || (id + rng.range(1..num_monkeys)) % num_monkeys;
+            let throw_to = (other_monkey(), other_monkey());
+            format!(
+                "Monkey {id}:\n  Starting items: {}\n  Operation: new = old {operation}\n  \
+                 Test: divisible by {}\n    If true: throw to monkey {}\n    \
+                 If false: throw to monkey {}\n",
+                items.join(", "),
+                rng.choose(&[2, 3, 5, 7, 11, 13, 17, 19, 23]),
+                throw_to.0,
+                throw_to.1
+            )
+        })
+        .collect();
+    notes.join("\n")
+}
+
+/// A heightmap of `size` rows (and four times as many columns) that rises towards E, with bumps
+/// that can't be climbed. The squares on the way from S to E (first along the row, then along the
+/// column) are never bumped, so E can always be reached.
+fn heightmap(rng: &mut Rng, size: usize) -> String {
+    let (width, height) = ((size * 4).max(48), size.max(5));
+    let start = (0, rng.range(0..height));
+    let end = (rng.range(width * 3 / 4..width), rng.range(0..height));
+    let distance = |(x, y): (usize, usize)| x.abs_diff(end.0) + y.abs_diff(end.1);
+    // the squares furthest from E are all at elevation a, which gives part two shorter paths
+    let ramp = distance(start) * 3 / 4;
+    let is_on_path = |(x, y): (usize, usize)| {
+        (y == start.1 && x <= end.0)
+            || (x == end.0 && y >= start.1.min(end.1) && y <= start.1.max(end.1))
+    };
+
+    lines((0..height).map(|y| {
+        (0..width)
+            .map(|x| {
+                if (x, y) == start {
+                    return 'S';
+                } else if (x, y) == end {
+                    return 'E';
+                }
+                // neighbors differ by at most one, as the ramp is at least 25 squares long
+                let mut elevation = 25 - (distance((x, y)) * 25 / ramp).min(25);
+                if !is_on_path((x, y)) && rng.chance(1, 4) {
+                    elevation = (elevation + rng.range(1..5)).min(25);
+                }
+                (b'a' + elevation as u8) as char
+            })
+            .collect()
+    }))
+}
+
+#[derive(Clone, PartialEq)]
+enum Packet {
+    Integer(u8),
+    List(Vec<Packet>),
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(value) => write!(f, "{value}"),
+            Self::List(items) => {
+                let items: Vec<String> = items.iter().map(Packet::to_string).collect();
+                write!(f, "[{}]", items.join(","))
+            }
+        }
+    }
+}
+
+fn random_list(rng: &mut Rng, depth: usize) -> Vec<Packet> {
+    (0..rng.range(0..6))
+        .map(|_| match depth < 4 && rng.chance(1, 3) {
+            true => Packet::List(random_list(rng, depth + 1)),
+            false => Packet::Integer(rng.range(0..11) as u8),
+        })
+        .collect()
+}
+
+/// Copy of the list with a small random change, so that pairs often start the same way
+fn mutate(rng: &mut Rng, list: &[Packet]) -> Vec<Packet> {
+    let mut list = list.to_vec();
+    if list.is_empty() || rng.chance(1, 4) {
+        list.push(Packet::Integer(rng.range(0..11) as u8));
+        return list;
+    }
+    let i = rng.range(0..list.len());
+    list[i] = match &list[i] {
+        Packet::List(items) if rng.chance(1, 2) => Packet::List(mutate(rng, items)),
+        Packet::Integer(value) if rng.chance(1, 2) => Packet::List(vec![Packet::Integer(*value)]),
+        _ => Packet::Integer(rng.range(0..11) as u8),
+    };
+    list
+}
+
+/// Pairs of different packets, where the second packet often starts like the first one
+fn packet_pairs(rng: &mut Rng, num_pairs: usize) -> String {
+    let pairs: Vec<String> = (0..num_pairs)
+        .map(|_| {
+            let left = random_list(rng, 0);
+            let right = loop {
+                let right = match rng.chance(2, 3) {
+                    true => mutate(rng, &left),
+                    false => random_list(rng, 0),
+                };
+                if right != left {
+                    break right;
+                }
+            };
+            format!("{}\n{}\n", Packet::List(left), Packet::List(right))
+        })
+        .collect();
+    pairs.join("\n")
+}
+
+/// Paths of horizontal and vertical lines below the sand source. The more paths, the deeper and
+/// wider the cave, so that sand still finds its way around the rocks into the abyss in part one.
+/// Beyond a few thousand paths the cave fills up, and part one ends when the source is blocked.
+fn rock_paths(rng: &mut Rng, num_paths: usize) -> String {
+    let depth = (40 + (num_paths as f64).sqrt() as usize * 10).min(480);
+    lines((0..num_paths).map(|_| {
+        let x = rng.range(500 - depth / 2..500 + depth / 2);
+        let mut point = (x, rng.range(25..depth));
+        let mut points = vec![point];
+        for i in 0..rng.range(1..6) {
+            let length = rng.range(1..8);
+            point = match (i % 2, rng.chance(1, 2)) {
+                (0, true) => (point.0 + length, point.1),
+                (0, false) => (point.0 - length, point.1),
+                (_, true) => (point.0, point.1 + length),
+                (_, false) => (point.0, point.1 - length),
+            };
+            points.push(point);
+        }
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+        points.join(" -> ")
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        for day in 1..=14 {
+            let input = generate(day, 20, 7).unwrap();
+            assert_eq!(Some(input), generate(day, 20, 7), "day {day}");
+            assert_ne!(generate(day, 20, 7), generate(day, 20, 8), "day {day}");
+        }
+        assert_eq!(generate(25, 20, 7), None);
+    }
+
+    #[test]
+    fn test_rucksacks() {
+        let input = generate(3, 30, 1).unwrap();
+        let rucksacks: Vec<&str> = input.lines().collect();
+        let common = |a: &str, b: &str| -> Vec<char> {
+            let mut common: Vec<char> = a.chars().filter(|&c| b.contains(c)).collect();
+            common.sort();
+            common.dedup();
+            common
+        };
+        for group in rucksacks.chunks(3) {
+            for rucksack in group {
+                let (left, right) = rucksack.split_at(rucksack.len() / 2);
+                assert_eq!(common(left, right).len(), 1, "{rucksack}");
+            }
+            let badges = common(
+                &common(group[0], group[1]).into_iter().collect::<String>(),
+                group[2],
+            );
+            assert_eq!(badges.len(), 1, "{group:?}");
+        }
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::collections::HashMap;
 
+use crate::image::Image;
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct GridCoord {
     pub x: usize,
@@ -14,12 +16,38 @@ impl From<(usize, usize)> for GridCoord {
     }
 }
 
-/// Implementation of a grid structure. Uses a HashMap as the underlying data structure.
-#[derive(Debug)]
+impl GridCoord {
+    /// The (up to) four coordinates directly above, below, left and right of this one. Neighbors
+    /// that would have a negative coordinate are skipped.
+    pub fn orthogonal_neighbors(&self) -> impl Iterator<Item = GridCoord> {
+        let (x, y) = (self.x, self.y);
+        [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| {
+                Some(GridCoord {
+                    x: x.checked_add_signed(dx)?,
+                    y: y.checked_add_signed(dy)?,
+                })
+            })
+    }
+}
+
+/// Common read access to the grid types, so algorithms (see `region`) work on both of them
+pub trait GridLike<T> {
+    /// Get the cell at this coordinate, if there is one
+    fn cell(&self, coord: &GridCoord) -> Option<&T>;
+
+    /// Coordinates of all cells, in row-major order
+    fn coords(&self) -> Vec<GridCoord>;
+}
+
+/// Implementation of a grid structure. Cells are stored row by row in a Vec, so all iterators
+/// go through the grid in a fixed, row-major order (left to right, top to bottom).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Grid<T> {
     pub width: usize,
     pub height: usize,
-    data: HashMap<GridCoord, T>,
+    data: Vec<T>,
 }
 
 impl<T> Grid<T>
@@ -27,16 +55,10 @@ where
     T: Default + Clone,
 {
     pub fn new(width: usize, height: usize) -> Self {
-        let mut data: HashMap<GridCoord, T> = HashMap::with_capacity(width * height);
-        for x in 0..width {
-            for y in 0..height {
-                data.insert(GridCoord { x, y }, T::default());
-            }
-        }
         Self {
             width,
             height,
-            data,
+            data: vec![T::default(); width * height],
         }
     }
 
@@ -44,31 +66,554 @@ where
         coord.x < self.width && coord.y < self.height
     }
 
+    fn index(&self, coord: &GridCoord) -> Option<usize> {
+        if self.is_in_bounds(coord) {
+            Some(coord.y * self.width + coord.x)
+        } else {
+            None
+        }
+    }
+
+    fn coord(&self, index: usize) -> GridCoord {
+        GridCoord {
+            x: index % self.width,
+            y: index / self.width,
+        }
+    }
+
     pub fn get_cell_mut(&mut self, coord: &GridCoord) -> Option<&mut T> {
-        if !self.is_in_bounds(coord) {
+        let index = self.index(coord)?;
+        self.data.get_mut(index)
+    }
+
+    pub fn get_cell(&self, coord: &GridCoord) -> Option<&T> {
+        self.data.get(self.index(coord)?)
+    }
+
+    pub fn insert_cell(&mut self, coord: GridCoord, cell: T) -> Option<()> {
+        *self.get_cell_mut(&coord)? = cell;
+        Some(())
+    }
+
+    /// Iterate over all cells with their coordinates, in row-major order
+    pub fn iter_all_cells(&self) -> impl Iterator<Item = (GridCoord, &T)> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (self.coord(index), cell))
+    }
+
+    /// Iterate over the rows, from top to bottom
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).filter_map(|y| self.row(y))
+    }
+
+    /// Iterate over the columns, from left to right. Each column goes from top to bottom.
+    pub fn iter_cols(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).filter_map(|x| self.col(x))
+    }
+
+    /// Get the cells in row `y`, from left to right
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        if y < self.height {
+            self.data.get(y * self.width..(y + 1) * self.width)
+        } else {
             None
+        }
+    }
+
+    /// Get the cells in column `x`, from top to bottom
+    pub fn col(&self, x: usize) -> Option<impl Iterator<Item = &T> + '_> {
+        if x < self.width {
+            Some(self.data.iter().skip(x).step_by(self.width))
         } else {
-            self.data.get_mut(coord)
+            None
+        }
+    }
+
+    /// Smallest area (top-left and bottom-right corners, inclusive) containing all non-default
+    /// cells
+    pub fn bounding_box(&self) -> Option<(GridCoord, GridCoord)>
+    where
+        T: PartialEq,
+    {
+        let default = T::default();
+        self.iter_all_cells()
+            .filter(|(_, cell)| **cell != default)
+            .fold(None, |bounds, (coord, _)| match bounds {
+                None => Some((coord, coord)),
+                Some((min, max)) => Some((
+                    GridCoord {
+                        x: min.x.min(coord.x),
+                        y: min.y.min(coord.y),
+                    },
+                    GridCoord {
+                        x: max.x.max(coord.x),
+                        y: max.y.max(coord.y),
+                    },
+                )),
+            })
+    }
+
+    /// Render the grid as text, with one line per row
+    pub fn render(&self, to_char: impl Fn(&T) -> char) -> String {
+        if self.width == 0 || self.height == 0 {
+            return String::new();
         }
+        self.render_area(
+            GridCoord { x: 0, y: 0 },
+            GridCoord {
+                x: self.width - 1,
+                y: self.height - 1,
+            },
+            to_char,
+        )
+    }
+
+    /// Render the grid as text, cropped to the cells that aren't set to the default value
+    pub fn render_cropped(&self, to_char: impl Fn(&T) -> char) -> String
+    where
+        T: PartialEq,
+    {
+        match self.bounding_box() {
+            Some((min, max)) => self.render_area(min, max, to_char),
+            None => String::new(),
+        }
+    }
+
+    fn render_area(&self, min: GridCoord, max: GridCoord, to_char: impl Fn(&T) -> char) -> String {
+        let mut output = String::with_capacity((max.x - min.x + 2) * (max.y - min.y + 1));
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                if let Some(cell) = self.get_cell(&GridCoord { x, y }) {
+                    output.push(to_char(cell));
+                }
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Convert the grid to an image with one pixel per cell, e.g. to save it as a PNG for debugging
+    pub fn to_image(&self, to_rgb: impl Fn(&T) -> [u8; 3]) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for (coord, cell) in self.iter_all_cells() {
+            image.set_pixel(coord.x, coord.y, to_rgb(cell));
+        }
+        image
+    }
+
+    /// Borrow the whole grid as a view, which can be rotated, flipped, etc. without copying cells
+    pub fn view(&self) -> GridView<'_, T> {
+        GridView {
+            grid: self,
+            origin: GridCoord { x: 0, y: 0 },
+            window_width: self.width,
+            window_height: self.height,
+            transposed: false,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    /// Copy of the grid rotated 90 degrees clockwise
+    pub fn rotate90(&self) -> Self {
+        self.view().rotate90().to_grid()
+    }
+
+    /// Copy of the grid mirrored horizontally (left and right are swapped)
+    pub fn flip_h(&self) -> Self {
+        self.view().flip_h().to_grid()
+    }
+
+    /// Copy of the grid mirrored vertically (top and bottom are swapped)
+    pub fn flip_v(&self) -> Self {
+        self.view().flip_v().to_grid()
+    }
+
+    /// Copy of the grid with rows and columns swapped
+    pub fn transpose(&self) -> Self {
+        self.view().transpose().to_grid()
+    }
+
+    /// Copy of the `width` x `height` rectangle with its top-left corner at (`x`, `y`), if it
+    /// fits inside the grid
+    pub fn window(&self, x: usize, y: usize, width: usize, height: usize) -> Option<Self> {
+        Some(self.view().window(x, y, width, height)?.to_grid())
+    }
+}
+
+impl<T> GridLike<T> for Grid<T>
+where
+    T: Default + Clone,
+{
+    fn cell(&self, coord: &GridCoord) -> Option<&T> {
+        self.get_cell(coord)
+    }
+
+    fn coords(&self) -> Vec<GridCoord> {
+        self.iter_all_cells().map(|(coord, _)| coord).collect()
+    }
+}
+
+/// Unbounded grid that only stores the cells that were inserted, using a HashMap. Useful when
+/// most of a large area is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    data: HashMap<GridCoord, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self {
+            data: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
     }
 
     pub fn get_cell(&self, coord: &GridCoord) -> Option<&T> {
+        self.data.get(coord)
+    }
+
+    pub fn get_cell_mut(&mut self, coord: &GridCoord) -> Option<&mut T> {
+        self.data.get_mut(coord)
+    }
+
+    /// Insert a cell, returning the previous one at this coordinate if there was one
+    pub fn insert_cell(&mut self, coord: GridCoord, cell: T) -> Option<T> {
+        self.data.insert(coord, cell)
+    }
+
+    pub fn remove_cell(&mut self, coord: &GridCoord) -> Option<T> {
+        self.data.remove(coord)
+    }
+
+    /// Iterate over all stored cells with their coordinates, in row-major order
+    pub fn iter_all_cells(&self) -> impl Iterator<Item = (GridCoord, &T)> + '_ {
+        let mut cells: Vec<(GridCoord, &T)> = self
+            .data
+            .iter()
+            .map(|(coord, cell)| (*coord, cell))
+            .collect();
+        cells.sort_unstable_by_key(|(coord, _)| (coord.y, coord.x));
+        cells.into_iter()
+    }
+}
+
+impl<T> GridLike<T> for SparseGrid<T> {
+    fn cell(&self, coord: &GridCoord) -> Option<&T> {
+        self.get_cell(coord)
+    }
+
+    fn coords(&self) -> Vec<GridCoord> {
+        self.iter_all_cells().map(|(coord, _)| coord).collect()
+    }
+}
+
+/// Read-only view of (a rectangle of) a grid, possibly rotated, flipped or transposed. No cells
+/// are copied: coordinates in the view are mapped back to the underlying grid on every access.
+#[derive(Debug, Clone, Copy)]
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    /** top-left corner of the window in the underlying grid */
+    origin: GridCoord,
+    /** size of the window in the underlying grid (before transposing) */
+    window_width: usize,
+    window_height: usize,
+    /** view coordinates are flipped first, then transposed, then offset by the origin */
+    transposed: bool,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+impl<'a, T> GridView<'a, T>
+where
+    T: Default + Clone,
+{
+    pub fn width(&self) -> usize {
+        if self.transposed {
+            self.window_height
+        } else {
+            self.window_width
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.transposed {
+            self.window_width
+        } else {
+            self.window_height
+        }
+    }
+
+    pub fn is_in_bounds(&self, coord: &GridCoord) -> bool {
+        coord.x < self.width() && coord.y < self.height()
+    }
+
+    /// Map a coordinate in the view to the coordinate in the underlying grid
+    fn grid_coord(&self, coord: &GridCoord) -> Option<GridCoord> {
         if !self.is_in_bounds(coord) {
-            None
+            return None;
+        }
+        let x = if self.flip_x {
+            self.width() - 1 - coord.x
         } else {
-            self.data.get(coord)
+            coord.x
+        };
+        let y = if self.flip_y {
+            self.height() - 1 - coord.y
+        } else {
+            coord.y
+        };
+        let (x, y) = if self.transposed { (y, x) } else { (x, y) };
+        Some(GridCoord {
+            x: self.origin.x + x,
+            y: self.origin.y + y,
+        })
+    }
+
+    pub fn get_cell(&self, coord: &GridCoord) -> Option<&'a T> {
+        self.grid.get_cell(&self.grid_coord(coord)?)
+    }
+
+    /// Iterate over all cells of the view with their (view) coordinates, in row-major order
+    pub fn iter_all_cells(&self) -> impl Iterator<Item = (GridCoord, &'a T)> + '_ {
+        (0..self.height()).flat_map(move |y| {
+            (0..self.width()).filter_map(move |x| {
+                let coord = GridCoord { x, y };
+                Some((coord, self.get_cell(&coord)?))
+            })
+        })
+    }
+
+    /// Rotate the view 90 degrees clockwise
+    pub fn rotate90(self) -> Self {
+        self.transpose().flip_h()
+    }
+
+    /// Mirror the view horizontally (left and right are swapped)
+    pub fn flip_h(self) -> Self {
+        Self {
+            flip_x: !self.flip_x,
+            ..self
         }
     }
 
-    pub fn insert_cell(&mut self, coord: GridCoord, cell: T) -> Option<()> {
-        if self.is_in_bounds(&coord) {
-            self.data.insert(coord, cell);
-            return Some(());
+    /// Mirror the view vertically (top and bottom are swapped)
+    pub fn flip_v(self) -> Self {
+        Self {
+            flip_y: !self.flip_y,
+            ..self
+        }
+    }
+
+    /// Swap rows and columns of the view
+    pub fn transpose(self) -> Self {
+        Self {
+            transposed: !self.transposed,
+            flip_x: self.flip_y,
+            flip_y: self.flip_x,
+            ..self
+        }
+    }
+
+    /// Narrow the view down to the `width` x `height` rectangle with its top-left corner at
+    /// (`x`, `y`) in view coordinates, if it fits inside the view
+    pub fn window(self, x: usize, y: usize, width: usize, height: usize) -> Option<Self> {
+        if x + width > self.width() || y + height > self.height() {
+            return None;
+        }
+        if width == 0 || height == 0 {
+            let (window_width, window_height) = if self.transposed {
+                (height, width)
+            } else {
+                (width, height)
+            };
+            return Some(Self {
+                window_width,
+                window_height,
+                ..self
+            });
+        }
+        // the flips and transposition are relative to the window, so the new window in the
+        // underlying grid is the rectangle spanned by the two mapped corners
+        let corner_a = self.grid_coord(&GridCoord { x, y })?;
+        let corner_b = self.grid_coord(&GridCoord {
+            x: x + width - 1,
+            y: y + height - 1,
+        })?;
+        Some(Self {
+            origin: GridCoord {
+                x: corner_a.x.min(corner_b.x),
+                y: corner_a.y.min(corner_b.y),
+            },
+            window_width: corner_a.x.abs_diff(corner_b.x) + 1,
+            window_height: corner_a.y.abs_diff(corner_b.y) + 1,
+            ..self
+        })
+    }
+
+    /// Copy the cells of the view into a new grid
+    pub fn to_grid(&self) -> Grid<T> {
+        let mut grid = Grid::new(self.width(), self.height());
+        for (coord, cell) in self.iter_all_cells() {
+            grid.insert_cell(coord, cell.clone());
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_grid() -> Grid<bool> {
+        let mut grid = Grid::new(5, 4);
+        grid.insert_cell((1, 1).into(), true);
+        grid.insert_cell((3, 2).into(), true);
+        grid
+    }
+
+    #[test]
+    fn test_render() {
+        let grid = build_grid();
+        let to_char = |cell: &bool| if *cell { '#' } else { '.' };
+        assert_eq!(grid.render(to_char), ".....\n.#...\n...#.\n.....\n");
+        assert_eq!(grid.render_cropped(to_char), "#..\n..#\n");
+        assert_eq!(Grid::<bool>::new(3, 3).render_cropped(to_char), "");
+    }
+
+    #[test]
+    fn test_to_image() {
+        let image = build_grid().to_image(|cell| if *cell { [255, 0, 0] } else { [0, 0, 0] });
+        assert_eq!((image.width, image.height), (5, 4));
+        assert_eq!(image.get_pixel(3, 2), Some([255, 0, 0]));
+        assert_eq!(image.get_pixel(2, 2), Some([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_iteration_order() {
+        let mut grid: Grid<u8> = Grid::new(3, 2);
+        for (value, coord) in [(0, 1), (1, 1), (2, 0), (2, 1)].into_iter().enumerate() {
+            grid.insert_cell(coord.into(), value as u8 + 1);
         }
-        None
+
+        let cells: Vec<(GridCoord, u8)> = grid
+            .iter_all_cells()
+            .map(|(coord, cell)| (coord, *cell))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                ((0, 0).into(), 0),
+                ((1, 0).into(), 0),
+                ((2, 0).into(), 3),
+                ((0, 1).into(), 1),
+                ((1, 1).into(), 2),
+                ((2, 1).into(), 4),
+            ]
+        );
+
+        let rows: Vec<&[u8]> = grid.iter_rows().collect();
+        assert_eq!(rows, vec![&[0, 0, 3][..], &[1, 2, 4][..]]);
+        let cols: Vec<Vec<u8>> = grid.iter_cols().map(|col| col.copied().collect()).collect();
+        assert_eq!(cols, vec![vec![0, 1], vec![0, 2], vec![3, 4]]);
+        assert_eq!(grid.row(1), Some(&[1, 2, 4][..]));
+        assert_eq!(grid.row(2), None);
+        assert_eq!(
+            grid.col(2).map(|col| col.copied().collect()),
+            Some(vec![3, 4])
+        );
+        assert!(grid.col(3).is_none());
     }
 
-    pub fn iter_all_cells(&self) -> std::collections::hash_map::Iter<GridCoord, T> {
-        self.data.iter()
+    /// 3x2 grid with distinct values:
+    /// 1 2 3
+    /// 4 5 6
+    fn build_numbered_grid() -> Grid<u8> {
+        let mut grid = Grid::new(3, 2);
+        for y in 0..2 {
+            for x in 0..3 {
+                grid.insert_cell((x, y).into(), (y * 3 + x + 1) as u8);
+            }
+        }
+        grid
+    }
+
+    fn rows(grid: &Grid<u8>) -> Vec<Vec<u8>> {
+        grid.iter_rows().map(|row| row.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_transforms() {
+        let grid = build_numbered_grid();
+        assert_eq!(
+            rows(&grid.rotate90()),
+            vec![vec![4, 1], vec![5, 2], vec![6, 3]]
+        );
+        assert_eq!(rows(&grid.flip_h()), vec![vec![3, 2, 1], vec![6, 5, 4]]);
+        assert_eq!(rows(&grid.flip_v()), vec![vec![4, 5, 6], vec![1, 2, 3]]);
+        assert_eq!(
+            rows(&grid.transpose()),
+            vec![vec![1, 4], vec![2, 5], vec![3, 6]]
+        );
+        assert_eq!(
+            rows(&grid.window(1, 0, 2, 2).unwrap()),
+            vec![vec![2, 3], vec![5, 6]]
+        );
+        assert_eq!(grid.window(2, 0, 2, 2), None);
+    }
+
+    #[test]
+    fn test_transform_round_trips() {
+        let grid = build_numbered_grid();
+        assert_eq!(grid.rotate90().rotate90().rotate90().rotate90(), grid);
+        assert_eq!(grid.flip_h().flip_h(), grid);
+        assert_eq!(grid.flip_v().flip_v(), grid);
+        assert_eq!(grid.transpose().transpose(), grid);
+        assert_eq!(grid.rotate90().rotate90(), grid.flip_h().flip_v());
+        assert_eq!(
+            grid.view().rotate90().rotate90().rotate90().to_grid(),
+            grid.rotate90().rotate90().rotate90()
+        );
+        assert_eq!(grid.window(0, 0, 3, 2).unwrap(), grid);
+    }
+
+    #[test]
+    fn test_view_window() {
+        let grid = build_numbered_grid();
+        // windows of a transformed view match windows of the transformed copy
+        let rotated = grid.rotate90();
+        for (x, y, width, height) in [(0, 0, 2, 3), (1, 1, 1, 2), (0, 2, 2, 1), (1, 0, 0, 3)] {
+            let view = grid.view().rotate90().window(x, y, width, height).unwrap();
+            assert_eq!((view.width(), view.height()), (width, height));
+            assert_eq!(Some(view.to_grid()), rotated.window(x, y, width, height));
+            assert_eq!(
+                view.flip_v().transpose().to_grid(),
+                rotated
+                    .window(x, y, width, height)
+                    .unwrap()
+                    .flip_v()
+                    .transpose()
+            );
+        }
+        assert!(grid.view().transpose().window(0, 0, 3, 2).is_none());
+        assert_eq!(
+            grid.view().flip_h().get_cell(&GridCoord { x: 0, y: 1 }),
+            Some(&6)
+        );
     }
 }
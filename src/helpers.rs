@@ -1,30 +1,306 @@
+use std::{
+    num::{IntErrorKind, ParseIntError},
+    str::FromStr,
+};
+
+use crate::error::ParseError;
+
 use nom::{
-    character::complete::digit1,
-    combinator::map_res,
+    bytes::complete::tag,
+    character::complete::{char, digit1, line_ending, multispace0, one_of, space0},
+    combinator::{all_consuming, not, opt, recognize},
+    error::{context, ErrorKind, ParseError as _, VerboseError},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
-pub fn parse_decimal_digits(input: &str) -> IResult<&str, &str> {
+/// Result of the parsers in this module. Errors keep the remaining input (and so the position)
+/// of every failed parser, plus the context added with `nom::error::context`.
+pub type ParseResult<'a, O> = IResult<&'a str, O, VerboseError<&'a str>>;
+
+pub fn parse_decimal_digits(input: &str) -> ParseResult<'_, &str> {
     digit1(input)
 }
 
-pub fn parse_int_decimal(input: &str) -> IResult<&str, i32> {
-    map_res(
-        parse_decimal_digits,
-        |out: &str| i32::from_str_radix(&out, 10),
-    )(input)
+/// Primitive integer types that can be parsed with `parse_num`
+pub trait Integer: FromStr<Err = ParseIntError> {
+    /// whether the type can hold negative numbers, i.e. whether a leading '-' is part of the number
+    const SIGNED: bool;
 }
 
-pub fn parse_u64_decimal(input: &str) -> IResult<&str, u64> {
-    map_res(
-        parse_decimal_digits,
-        |out: &str| u64::from_str_radix(&out, 10),
-    )(input)
+macro_rules! impl_integer {
+    ($signed:literal: $($int:ty),*) => {
+        $(impl Integer for $int {
+            const SIGNED: bool = $signed;
+        })*
+    };
+}
+impl_integer!(true: i8, i16, i32, i64, i128, isize);
+impl_integer!(false: u8, u16, u32, u64, u128, usize);
+
+/// Parse a decimal integer of any primitive type, with an optional sign ('-' is only accepted for
+/// signed types). A number that doesn't fit in the type fails with `ErrorKind::TooLarge`, anything
+/// else that isn't a number fails with `ErrorKind::Digit`.
+pub fn parse_num<T: Integer>(input: &str) -> ParseResult<'_, T> {
+    let sign = if T::SIGNED { "+-" } else { "+" };
+    let (rest, digits) = recognize(pair(opt(one_of(sign)), digit1))(input)?;
+    match digits.parse::<T>() {
+        Ok(num) => Ok((rest, num)),
+        Err(e) => {
+            let kind = match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => ErrorKind::TooLarge,
+                _ => ErrorKind::Digit,
+            };
+            Err(nom::Err::Error(VerboseError::from_error_kind(input, kind)))
+        }
+    }
+}
+
+/// Run a parser on the whole input. Anything but trailing whitespace left after parsing is an
+/// error, and errors are converted to a `ParseError` report.
+pub fn parse_all<'a, O>(
+    input: &'a str,
+    parser: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> Result<O, ParseError> {
+    let (_, output) = all_consuming(terminated(parser, multispace0))(input)
+        .map_err(|e| ParseError::from_nom(input, e))?;
+    Ok(output)
 }
 
-pub fn parse_usize_decimal(input: &str) -> IResult<&str, usize> {
-    map_res(
-        parse_decimal_digits,
-        |out: &str| usize::from_str_radix(&out, 10),
+/// Parse a coordinate pair like `498,4`
+pub fn parse_coord<T: Integer>(input: &str) -> ParseResult<'_, (T, T)> {
+    context(
+        "coordinate pair",
+        separated_pair(parse_num, char(','), parse_num),
     )(input)
 }
+
+/// Parse a list of items separated by `separator`. Unlike `nom::multi::separated_list1`, once a
+/// separator is found the next item has to parse, so the error points at the broken item.
+fn separated_items<'a, O, S>(
+    mut separator: impl FnMut(&'a str) -> ParseResult<'a, S>,
+    mut item: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    move |input| {
+        let (mut input, first_item) = item(input)?;
+        let mut items = vec![first_item];
+        while let Ok((rest, _)) = separator(input) {
+            let (rest, next_item) = item(rest)?;
+            items.push(next_item);
+            input = rest;
+        }
+        Ok((input, items))
+    }
+}
+
+/// Parse a comma separated list like `79, 98` or `1,2,3`
+pub fn comma_list<'a, O>(
+    item: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    separated_items(pair(char(','), space0), item)
+}
+
+/// Parse a path of items separated by arrows, like `498,4 -> 498,6 -> 496,6`
+pub fn arrow_path<'a, O>(
+    item: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    separated_items(delimited(space0, tag("->"), space0), item)
+}
+
+/// Parse a `key: value` line (with optional indentation) and return the value
+pub fn key_value<'a, O>(
+    key: &'static str,
+    value: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, O> {
+    preceded(
+        space0,
+        context(key, preceded(tuple((tag(key), char(':'), space0)), value)),
+    )
+}
+
+/// Parse one item per line, stopping at a blank line or the end of the input. Trailing
+/// whitespace at the end of the input (e.g. a final newline) is consumed.
+pub fn lines_of<'a, O>(
+    item: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    separated_until_end(terminated(line_ending, not(line_ending)), item)
+}
+
+/// Parse blocks separated by blank lines, like the monkeys of day 11. Trailing whitespace at the
+/// end of the input (e.g. a final newline) is consumed.
+pub fn blocks<'a, O>(
+    block: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    separated_until_end(pair(line_ending, line_ending), block)
+}
+
+/// Parse items separated by `separator`, until there's no separator left. If only whitespace is
+/// left after the last item, it's consumed as well.
+fn separated_until_end<'a, O, S>(
+    mut separator: impl FnMut(&'a str) -> ParseResult<'a, S>,
+    mut item: impl FnMut(&'a str) -> ParseResult<'a, O>,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Vec<O>> {
+    move |mut input| {
+        let mut items = vec![];
+        loop {
+            let (rest, next_item) = item(input)?;
+            items.push(next_item);
+            if rest.trim().is_empty() {
+                return Ok((&rest[rest.len()..], items));
+            }
+            match separator(rest) {
+                Ok((rest, _)) => input = rest,
+                Err(_) => return Ok((rest, items)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::VerboseErrorKind;
+
+    fn error_kind<T: Integer>(input: &str) -> Option<ErrorKind> {
+        match parse_num::<T>(input) {
+            Err(nom::Err::Error(e)) => match e.errors.first() {
+                Some((_, VerboseErrorKind::Nom(kind))) => Some(*kind),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Position of the innermost error in the input
+    fn error_offset<O>(input: &str, result: ParseResult<O>) -> Option<usize> {
+        match result {
+            Err(nom::Err::Error(e)) => Some(input.len() - e.errors.first()?.0.len()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_num() {
+        assert_eq!(parse_num::<i32>("-5 rest"), Ok((" rest", -5)));
+        assert_eq!(parse_num::<i32>("+12,"), Ok((",", 12)));
+        assert_eq!(parse_num::<u8>("255"), Ok(("", 255)));
+        assert_eq!(parse_num::<i8>("-128"), Ok(("", -128)));
+        assert_eq!(parse_num::<u64>("18446744073709551615"), Ok(("", u64::MAX)));
+        assert_eq!(parse_num::<i128>("-1"), Ok(("", -1)));
+        assert_eq!(parse_num::<usize>("007"), Ok(("", 7)));
+        // unsigned types don't treat '-' as part of the number
+        assert_eq!(parse_num::<u16>("2-4"), Ok(("-4", 2)));
+        assert_eq!(error_kind::<u32>("-4"), Some(ErrorKind::Digit));
+    }
+
+    #[test]
+    fn test_parse_num_errors() {
+        assert_eq!(error_kind::<u8>("256"), Some(ErrorKind::TooLarge));
+        assert_eq!(error_kind::<i8>("-129"), Some(ErrorKind::TooLarge));
+        assert_eq!(
+            error_kind::<i64>("99999999999999999999"),
+            Some(ErrorKind::TooLarge)
+        );
+        assert_eq!(error_kind::<i32>("abc"), Some(ErrorKind::Digit));
+        assert_eq!(error_kind::<i32>("-"), Some(ErrorKind::Digit));
+        assert_eq!(error_kind::<i32>(""), Some(ErrorKind::Digit));
+        assert_eq!(
+            parse_num::<u8>("300"),
+            Err(nom::Err::Error(VerboseError {
+                errors: vec![("300", VerboseErrorKind::Nom(ErrorKind::TooLarge))]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_coord() {
+        assert_eq!(parse_coord::<usize>("498,4 ->"), Ok((" ->", (498, 4))));
+        assert_eq!(parse_coord::<i32>("-1,-2"), Ok(("", (-1, -2))));
+        assert_eq!(error_offset("12,x", parse_coord::<u32>("12,x")), Some(3));
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(
+            comma_list(parse_num::<u64>)("79, 98\n"),
+            Ok(("\n", vec![79, 98]))
+        );
+        assert_eq!(
+            comma_list(parse_num::<u8>)("1,2,3"),
+            Ok(("", vec![1, 2, 3]))
+        );
+        assert_eq!(
+            error_offset("1, 2, x", comma_list(parse_num::<u8>)("1, 2, x")),
+            Some(6)
+        );
+
+        let path = "498,4 -> 498,6 -> 496,6";
+        assert_eq!(
+            arrow_path(parse_coord::<usize>)(path),
+            Ok(("", vec![(498, 4), (498, 6), (496, 6)]))
+        );
+        assert_eq!(
+            error_offset("1,2 -> 3", arrow_path(parse_coord::<u8>)("1,2 -> 3")),
+            Some(8)
+        );
+    }
+
+    #[test]
+    fn test_key_value() {
+        let mut starting_items = key_value("Starting items", comma_list(parse_num::<u64>));
+        assert_eq!(
+            starting_items("  Starting items: 79, 98"),
+            Ok(("", vec![79, 98]))
+        );
+        assert_eq!(starting_items("Starting items:5"), Ok(("", vec![5])));
+        assert_eq!(
+            error_offset("  Starting itens: 1", starting_items("  Starting itens: 1")),
+            Some(2)
+        );
+        match starting_items("Starting items: x") {
+            Err(nom::Err::Error(e)) => assert_eq!(
+                e.errors.last(),
+                Some(&(
+                    "Starting items: x",
+                    VerboseErrorKind::Context("Starting items")
+                ))
+            ),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_lines_and_blocks() {
+        assert_eq!(
+            lines_of(parse_num::<u32>)("1\n2\r\n3\n"),
+            Ok(("", vec![1, 2, 3]))
+        );
+        assert_eq!(
+            error_offset("1\n2\nx", lines_of(parse_num::<u32>)("1\n2\nx")),
+            Some(4)
+        );
+
+        let input = "1000\n2000\n\n4000\n\n5000\n6000\n";
+        assert_eq!(
+            blocks(lines_of(parse_num::<u32>))(input),
+            Ok(("", vec![vec![1000, 2000], vec![4000], vec![5000, 6000]]))
+        );
+        let bad_input = "1\n\n2\nx";
+        assert_eq!(
+            error_offset(bad_input, blocks(lines_of(parse_num::<u32>))(bad_input)),
+            Some(5)
+        );
+        let missing_blank_line = "1\n\n2 3";
+        assert_eq!(
+            blocks(parse_num::<u32>)(missing_blank_line),
+            Ok((" 3", vec![1, 2]))
+        );
+        assert_eq!(
+            error_offset(
+                missing_blank_line,
+                all_consuming(blocks(parse_num::<u32>))(missing_blank_line)
+            ),
+            Some(4)
+        );
+    }
+}
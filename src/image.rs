@@ -0,0 +1,201 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Simple RGB image buffer that can be written out as PPM or PNG, without any extra dependencies.
+/// Mainly useful to dump a grid to a file for debugging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    /// Create a black image of the given size
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0, 0, 0]; width * height],
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<[u8; 3]> {
+        if x < self.width && y < self.height {
+            self.pixels.get(y * self.width + x).copied()
+        } else {
+            None
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, rgb: [u8; 3]) -> Option<()> {
+        if x < self.width && y < self.height {
+            *self.pixels.get_mut(y * self.width + x)? = rgb;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Write the image in the binary PPM (P6) format
+    pub fn write_ppm(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in &self.pixels {
+            writer.write_all(pixel)?;
+        }
+        Ok(())
+    }
+
+    /// Write the image in the PNG format. The image data isn't compressed (deflate 'stored'
+    /// blocks), so files are large but any image viewer can open them.
+    pub fn write_png(&self, writer: &mut impl Write) -> io::Result<()> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "image too large for PNG");
+        let width = u32::try_from(self.width).map_err(|_| too_large())?;
+        let height = u32::try_from(self.height).map_err(|_| too_large())?;
+
+        writer.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        // bit depth 8, color type 2 (RGB), default compression/filter, no interlacing
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_png_chunk(writer, b"IHDR", &header)?;
+
+        // every scanline starts with its filter type (0 = none)
+        let mut raw_data = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            raw_data.push(0);
+            for pixel in row {
+                raw_data.extend_from_slice(pixel);
+            }
+        }
+        write_png_chunk(writer, b"IDAT", &zlib_stored(&raw_data))?;
+        write_png_chunk(writer, b"IEND", &[])
+    }
+
+    /// Save the image to a file. The format is picked from the file extension (`.png` or `.ppm`).
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let is_png = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => true,
+            Some("ppm") => false,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "image file extension should be .png or .ppm",
+                ))
+            }
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        if is_png {
+            self.write_png(&mut writer)?;
+        } else {
+            self.write_ppm(&mut writer)?;
+        }
+        writer.flush()
+    }
+}
+
+fn write_png_chunk(writer: &mut impl Write, chunk_type: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(data.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "PNG chunk too large"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(chunk_type)?;
+    writer.write_all(data)?;
+    let crc = crc32(chunk_type.iter().chain(data));
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// Wrap the data in a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK_SIZE: usize = u16::MAX as usize;
+
+    let mut output = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK_SIZE * 5 + 11);
+    output.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_BLOCK_SIZE).peekable();
+    if blocks.peek().is_none() {
+        // an empty stream still needs one (final) block
+        output.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+        output.push(u8::from(is_final));
+        output.extend_from_slice(&len.to_le_bytes());
+        output.extend_from_slice(&(!len).to_le_bytes());
+        output.extend_from_slice(block);
+    }
+    output.extend_from_slice(&adler32(data).to_be_bytes());
+    output
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (a, b) = bytes.iter().fold((1_u32, 0_u32), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % MOD_ADLER;
+        (a, (b + a) % MOD_ADLER)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut image = Image::new(2, 1);
+        image.set_pixel(1, 0, [255, 0, 10]);
+        let mut output = vec![];
+        image.write_ppm(&mut output).unwrap();
+        assert_eq!(output, b"P6\n2 1\n255\n\0\0\0\xff\0\x0a");
+    }
+
+    #[test]
+    fn test_write_png() {
+        let mut image = Image::new(1, 1);
+        image.set_pixel(0, 0, [255, 255, 255]);
+        let mut output = vec![];
+        image.write_png(&mut output).unwrap();
+
+        assert_eq!(&output[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&output[12..16], b"IHDR");
+        assert_eq!(
+            &output[output.len() - 12..],
+            b"\0\0\0\0IEND\xae\x42\x60\x82"
+        );
+        // IDAT: zlib header, one final stored block with the filter byte + RGB, adler32
+        let idat_start = 8 + 12 + 13;
+        assert_eq!(&output[idat_start + 4..idat_start + 8], b"IDAT");
+        assert_eq!(
+            &output[idat_start + 8..idat_start + 8 + 15],
+            b"\x78\x01\x01\x04\x00\xfb\xff\x00\xff\xff\xff\x05\xfe\x02\xfe"
+        );
+    }
+}
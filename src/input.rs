@@ -0,0 +1,98 @@
+use std::{fmt, ops::Deref};
+
+/// Puzzle input with normalised line endings: Windows line endings (`\r\n`) become `\n`, and
+/// trailing whitespace at the end of the input (e.g. the final newline) is removed. Derefs to
+/// `str`, so all the usual string methods work as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input(String);
+
+impl Input {
+    pub fn new(raw: &str) -> Self {
+        let normalised = raw.replace("\r\n", "\n");
+        Self(normalised.trim_end().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The lines of the input
+    pub fn lines(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.lines()
+    }
+
+    /// Groups of lines separated by blank lines (lines with only whitespace count as blank)
+    pub fn blocks(&self) -> impl Iterator<Item = &str> + '_ {
+        let text = self.0.as_str();
+        let mut blocks = vec![];
+        let mut block: Option<(usize, usize)> = None;
+        let mut offset = 0;
+        for line in text.split('\n') {
+            if line.trim().is_empty() {
+                if let Some((start, end)) = block.take() {
+                    blocks.push(&text[start..end]);
+                }
+            } else {
+                let start = block.map_or(offset, |(start, _)| start);
+                block = Some((start, offset + line.len()));
+            }
+            offset += line.len() + 1;
+        }
+        if let Some((start, end)) = block {
+            blocks.push(&text[start..end]);
+        }
+        blocks.into_iter()
+    }
+}
+
+impl From<&str> for Input {
+    fn from(raw: &str) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl Deref for Input {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalise() {
+        let input = Input::new("1000\r\n2000\r\n\r\n3000\r\n\r\n");
+        assert_eq!(input.as_str(), "1000\n2000\n\n3000");
+        assert_eq!(
+            input.lines().collect::<Vec<_>>(),
+            vec!["1000", "2000", "", "3000"]
+        );
+        assert_eq!(input.as_bytes().last(), Some(&b'0'));
+        // leading whitespace is significant, e.g. for the crate stacks of day 05
+        assert_eq!(
+            Input::new("    [D]\n[N] [C]\n").as_str(),
+            "    [D]\n[N] [C]"
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let input = Input::new("1000\n2000\n\n4000\n  \n\n5000\n6000\n\n");
+        assert_eq!(
+            input.blocks().collect::<Vec<_>>(),
+            vec!["1000\n2000", "4000", "5000\n6000"]
+        );
+        assert_eq!(Input::new("abc").blocks().collect::<Vec<_>>(), vec!["abc"]);
+        assert_eq!(Input::new("\n\n").blocks().count(), 0);
+    }
+}
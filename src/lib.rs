@@ -4,51 +4,155 @@
  * Prefer `./helpers.rs` if you want to extract code from your solutions.
  */
 use std::env;
+use std::error::Error;
+use std::fmt::Display;
 use std::fs;
 
+pub mod error;
+#[cfg(feature = "testing")]
+pub mod fuzz;
+pub mod generate;
 pub mod grid;
 pub mod helpers;
+pub mod image;
+mod input;
+pub mod ocr;
+pub mod profile;
+#[cfg(feature = "testing")]
+pub mod prop;
+pub mod random;
+pub mod region;
+pub mod search;
+
+pub use input::Input;
 
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
 pub const ANSI_RESET: &str = "\x1b[0m";
 
+/// Outcome of running a solver, see `Solution`
+pub enum Outcome<T> {
+    Solved(T),
+    NotSolved,
+    Failed(String),
+}
+
+/// Error for parts that aren't solved yet. `solve!` prints "not solved." for it, instead of an
+/// error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsolved;
+
+impl Display for Unsolved {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not solved")
+    }
+}
+
+/// Return types accepted from solvers by `solve!`: `Result<T, E>` with a `SolverError`, or
+/// `Option<T>` where `None` means the part isn't solved yet.
+pub trait Solution {
+    type Answer: Display;
+
+    fn into_outcome(self) -> Outcome<Self::Answer>;
+}
+
+impl<T: Display> Solution for Option<T> {
+    type Answer = T;
+
+    fn into_outcome(self) -> Outcome<T> {
+        match self {
+            Some(answer) => Outcome::Solved(answer),
+            None => Outcome::NotSolved,
+        }
+    }
+}
+
+impl<T: Display, E: SolverError> Solution for Result<T, E> {
+    type Answer = T;
+
+    fn into_outcome(self) -> Outcome<T> {
+        match self {
+            Ok(answer) => Outcome::Solved(answer),
+            Err(e) => e.into_outcome(),
+        }
+    }
+}
+
+/// Errors that solvers can return: `Unsolved` for parts that aren't solved yet, or anything that
+/// converts into a `Box<dyn Error>` (any `Error`, boxed errors and plain strings), which is
+/// reported with all of its causes. A type that is only `Display` has no causes to report, and
+/// can't be told apart from `Unsolved` without specialization, so errors have to be `Error`s.
+pub trait SolverError {
+    fn into_outcome<T>(self) -> Outcome<T>;
+}
+
+impl SolverError for Unsolved {
+    fn into_outcome<T>(self) -> Outcome<T> {
+        Outcome::NotSolved
+    }
+}
+
+impl<E: Into<Box<dyn Error>>> SolverError for E {
+    fn into_outcome<T>(self) -> Outcome<T> {
+        Outcome::Failed(error_chain(&*self.into()))
+    }
+}
+
+/// Format an error with all of its causes
+fn error_chain(e: &dyn Error) -> String {
+    let mut report = e.to_string();
+    let mut cause = e.source();
+    while let Some(e) = cause {
+        report += &format!("\n  caused by: {e}");
+        cause = e.source();
+    }
+    report
+}
+
 #[macro_export]
 macro_rules! solve {
     ($part:expr, $solver:ident, $input:expr) => {{
-        use advent_of_code::{ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
-        use std::fmt::Display;
+        use advent_of_code::{Input, Outcome, Solution, ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
         use std::time::Instant;
 
-        fn print_result<T: Display>(func: impl FnOnce(&str) -> Option<T>, input: &str) {
+        fn print_result<S: Solution>(func: impl FnOnce(&Input) -> S, input: &Input) {
             let timer = Instant::now();
-            let result = func(input);
+            let result = func(input).into_outcome();
             let elapsed = timer.elapsed();
             match result {
-                Some(result) => {
+                Outcome::Solved(result) => {
                     println!(
                         "{} {}(elapsed: {:.2?}){}",
                         result, ANSI_ITALIC, elapsed, ANSI_RESET
                     );
                 }
-                None => {
+                Outcome::NotSolved => {
                     println!("not solved.")
                 }
+                Outcome::Failed(report) => {
+                    println!("{}error:{} {}", ANSI_BOLD, ANSI_RESET, report)
+                }
             }
         }
 
         println!("🎄 {}Part {}{} 🎄", ANSI_BOLD, $part, ANSI_RESET);
         print_result($solver, $input);
+        #[cfg(feature = "profile")]
+        print!(
+            "{}",
+            advent_of_code::profile::report(&advent_of_code::profile::take_phases())
+        );
     }};
 }
 
-pub fn read_file(folder: &str, day: u8) -> String {
+/// Read the input (or example) file of a day, normalised with `Input`
+pub fn read_file(folder: &str, day: u8) -> Input {
     let cwd = env::current_dir().unwrap();
 
     let filepath = cwd.join("src").join(folder).join(format!("{day:02}.txt"));
 
     let f = fs::read_to_string(filepath);
-    f.expect("could not open input file")
+    Input::new(&f.expect("could not open input file"))
 }
 
 fn parse_time(val: &str, postfix: &str) -> f64 {
@@ -96,6 +200,57 @@ macro_rules! assert_approx_eq {
 mod tests {
     use super::*;
 
+    #[derive(Debug)]
+    struct WrappedError(std::num::ParseIntError);
+
+    impl Display for WrappedError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "invalid calories")
+        }
+    }
+
+    impl Error for WrappedError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    fn failure<T: Display>(outcome: Outcome<T>) -> Option<String> {
+        match outcome {
+            Outcome::Failed(report) => Some(report),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_solution_outcomes() {
+        assert!(matches!(
+            Ok::<u32, Unsolved>(5).into_outcome(),
+            Outcome::Solved(5)
+        ));
+        assert!(matches!(
+            Err::<u32, Unsolved>(Unsolved).into_outcome(),
+            Outcome::NotSolved
+        ));
+        assert!(matches!(None::<u32>.into_outcome(), Outcome::NotSolved));
+        assert_eq!(
+            failure(Err::<u32, &str>("bad input").into_outcome()),
+            Some("bad input".to_string())
+        );
+
+        let cause = "x".parse::<u32>().unwrap_err();
+        let report = "invalid calories\n  caused by: invalid digit found in string";
+        assert_eq!(
+            failure(Err::<u32, _>(WrappedError(cause.clone())).into_outcome()),
+            Some(report.to_string())
+        );
+        let boxed: Box<dyn Error> = Box::new(WrappedError(cause));
+        assert_eq!(
+            failure(Err::<u32, _>(boxed).into_outcome()),
+            Some(report.to_string())
+        );
+    }
+
     #[test]
     fn test_parse_exec_time() {
         assert_approx_eq!(
@@ -132,6 +287,7 @@ pub mod aoc_cli {
         process::{Command, Output, Stdio},
     };
 
+    #[derive(Debug)]
     pub enum AocCliError {
         CommandNotFound,
         CommandNotCallable,
@@ -152,6 +308,8 @@ pub mod aoc_cli {
         }
     }
 
+    impl std::error::Error for AocCliError {}
+
     pub fn check() -> Result<(), AocCliError> {
         Command::new("aoc")
             .arg("-V")
@@ -0,0 +1,251 @@
+//! Reading the block letters that some puzzles draw on a screen, so the answer can be submitted
+//! as text. Two fonts are known: the 4x6 one (e.g. 2022 day 10) and the 6x10 one (2018 day 10).
+//! Only the letters that have shown up in puzzles are in the fonts.
+
+use std::fmt;
+
+use crate::grid::Grid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    /** letters of 4x6 pixels every 5 columns, so there's a blank column between them, except
+    after Y which is 5 wide */
+    Small,
+    /** letters of 6x10 pixels every 8 columns */
+    Large,
+}
+
+const SMALL_GLYPHS: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[rustfmt::skip]
+const LARGE_GLYPHS: &[(char, [&str; 10])] = &[
+    ('A', ["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', ["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', [".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', [".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', ["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', ["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', ["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', ["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', ["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', ["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', ["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', ["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', ["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+impl Font {
+    pub fn height(self) -> usize {
+        match self {
+            Font::Small => 6,
+            Font::Large => 10,
+        }
+    }
+
+    /// Columns from the start of one letter to the start of the next
+    fn pitch(self) -> usize {
+        match self {
+            Font::Small => 5,
+            Font::Large => 8,
+        }
+    }
+
+    /// The font with letters as high as the screen
+    fn for_height(height: usize) -> Option<Font> {
+        [Font::Small, Font::Large]
+            .into_iter()
+            .find(|font| font.height() == height)
+    }
+
+    /// The rows of every letter of the font
+    fn glyphs(self) -> impl Iterator<Item = (char, &'static [&'static str])> {
+        let (small, large) = match self {
+            Font::Small => (SMALL_GLYPHS, &[][..]),
+            Font::Large => (&[][..], LARGE_GLYPHS),
+        };
+        small
+            .iter()
+            .map(|(letter, rows)| (*letter, &rows[..]))
+            .chain(large.iter().map(|(letter, rows)| (*letter, &rows[..])))
+    }
+}
+
+/// Read the letters on a screen of lit (`true`) pixels. The font is picked from the height of the
+/// screen. Returns `None` if there are no letters, or any of them isn't in the font.
+pub fn read(screen: &Grid<bool>) -> Option<String> {
+    read_pixels(screen.width, screen.height, |x, y| {
+        screen.get_cell(&(x, y).into()) == Some(&true)
+    })
+}
+
+/// Read the letters on a screen drawn as text, with `#` for lit pixels, like the output of
+/// `Grid::render`. See `read`.
+pub fn read_bitmap(bitmap: &str) -> Option<String> {
+    let rows: Vec<&[u8]> = bitmap.lines().map(str::as_bytes).collect();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    read_pixels(width, rows.len(), |x, y| rows[y].get(x) == Some(&b'#'))
+}
+
+/// Read the letters at every `pitch` columns from the left of the screen, skipping blank ones.
+/// The letters have to be cut at fixed columns, as a letter as wide as the pitch (Y) touches the
+/// next one.
+fn read_pixels(
+    width: usize,
+    height: usize,
+    is_lit: impl Fn(usize, usize) -> bool,
+) -> Option<String> {
+    let font = Font::for_height(height)?;
+    let pitch = font.pitch();
+    let mut letters = String::new();
+    for start in (0..width).step_by(pitch) {
+        // the last letter may be cut off where its blank columns would be
+        let is_lit = |x: usize, y: usize| start + x < width && is_lit(start + x, y);
+        if (0..pitch).all(|x| (0..height).all(|y| !is_lit(x, y))) {
+            continue;
+        }
+        let (letter, _) = font.glyphs().find(|(_, rows)| {
+            rows.iter().enumerate().all(|(y, row)| {
+                (0..pitch).all(|x| (row.as_bytes().get(x) == Some(&b'#')) == is_lit(x, y))
+            })
+        })?;
+        letters.push(letter);
+    }
+    (!letters.is_empty()).then_some(letters)
+}
+
+/// Draw text in a font, the way a puzzle would. Returns `None` for letters that aren't in the font.
+pub fn render(text: &str, font: Font) -> Option<Grid<bool>> {
+    let pitch = font.pitch();
+    let mut screen = Grid::new(text.chars().count() * pitch, font.height());
+    for (i, letter) in text.chars().enumerate() {
+        let (_, rows) = font.glyphs().find(|(glyph, _)| *glyph == letter)?;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, pixel) in row.bytes().enumerate() {
+                screen.insert_cell((i * pitch + x, y).into(), pixel == b'#')?;
+            }
+        }
+    }
+    Some(screen)
+}
+
+/// Letters drawn on a screen. Displays the letters if they could be read, or else the screen
+/// itself. With `{:#}` the screen is always shown, above the letters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    pub letters: Option<String>,
+    /** the screen as text, with `#` for lit pixels */
+    pub bitmap: String,
+}
+
+impl Screen {
+    pub fn new(screen: &Grid<bool>) -> Self {
+        Self {
+            letters: read(screen),
+            bitmap: screen.render(|is_lit| if *is_lit { '#' } else { '.' }),
+        }
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.letters {
+            Some(letters) if f.alternate() => write!(f, "{}{letters}", self.bitmap),
+            Some(letters) => write!(f, "{letters}"),
+            None => write!(f, "{}", self.bitmap),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        for font in [Font::Small, Font::Large] {
+            let letters: String = font.glyphs().map(|(letter, _)| letter).collect();
+            let screen = render(&letters, font).unwrap();
+            assert_eq!(read(&screen), Some(letters.clone()), "{font:?}");
+            assert_eq!(
+                read_bitmap(&screen.render(|is_lit| if *is_lit { '#' } else { '.' })),
+                Some(letters)
+            );
+        }
+
+        let bitmap = "\
+###..####.#..#.\n\
+#..#.#....#..#.\n\
+#..#.###..####.\n\
+###..#....#..#.\n\
+#.#..#....#..#.\n\
+#..#.####.#..#.\n";
+        assert_eq!(read_bitmap(bitmap), Some("REH".to_string()));
+        // a pixel off, a screen of the wrong height, and an empty screen
+        assert_eq!(read_bitmap(&bitmap.replacen('.', "#", 1)), None);
+        assert_eq!(read_bitmap(&bitmap[..bitmap.len() - 16]), None);
+        assert_eq!(read_bitmap(&".....\n".repeat(6)), None);
+        assert!(render("AQ", Font::Small).is_none());
+    }
+
+    #[test]
+    fn test_alphabet() {
+        // every letter known to show up in puzzles, which all have to be in the fonts
+        for (font, alphabet) in [
+            (Font::Small, "ABCEFGHIJKLOPRSUYZ"),
+            (Font::Large, "ABCEFGHJKLNPRXZ"),
+        ] {
+            let screen = render(alphabet, font).unwrap();
+            assert_eq!(read(&screen).as_deref(), Some(alphabet), "{font:?}");
+        }
+
+        // Y fills its gap column, and touches the next letter like on a real screen
+        let bitmap = "\
+#...#####.\n\
+#...#...#.\n\
+.#.#...#..\n\
+..#...#...\n\
+..#..#....\n\
+..#..####.\n";
+        assert_eq!(read_bitmap(bitmap).as_deref(), Some("YZ"));
+        let screen = render("YZ", Font::Small).unwrap();
+        assert_eq!(
+            screen.render(|is_lit| if *is_lit { '#' } else { '.' }),
+            bitmap
+        );
+    }
+
+    #[test]
+    fn test_screen() {
+        let mut smudged = render("HE", Font::Large).unwrap();
+        smudged.insert_cell((2, 0).into(), true);
+        let screen = Screen::new(&smudged);
+        assert_eq!(screen.letters, None);
+        assert_eq!(screen.to_string(), screen.bitmap);
+
+        let screen = Screen::new(&render("HEL", Font::Small).unwrap());
+        assert_eq!(screen.to_string(), "HEL");
+        assert_eq!(format!("{screen:#}"), format!("{}HEL", screen.bitmap));
+        assert_eq!(screen.bitmap.lines().next(), Some("#..#.####.#...."));
+    }
+}
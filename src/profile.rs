@@ -0,0 +1,181 @@
+//! Lightweight timing of the phases of a solver. Wrap a phase in `time_phase!`, and `solve!`
+//! prints how long every phase took below the result of the part:
+//!
+//! ```text
+//! let tiles = time_phase!("parse", parse_tiles(input)?);
+//! ```
+//!
+//! The timing is only compiled in with the `profile` feature, e.g.
+//! `cargo solve 14 --release --features profile`. Without it, `time_phase!` is just the wrapped
+//! expression.
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// Time spent in a phase, over all the times it ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phase {
+    pub name: &'static str,
+    /** index of the phase this one ran in, as phases are told apart by name and parent */
+    pub parent: Option<usize>,
+    /** number of phases this one ran in */
+    pub depth: usize,
+    pub calls: u32,
+    pub total: Duration,
+}
+
+thread_local! {
+    static PHASES: RefCell<Vec<Phase>> = const { RefCell::new(vec![]) };
+    /** index of the innermost phase that is running */
+    static CURRENT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Time a phase of a solver and return the value of the expression. Phases can be nested, and
+/// a phase that runs several times (e.g. in a loop) is added up.
+#[macro_export]
+macro_rules! time_phase {
+    ($name:expr, $body:expr) => {{
+        #[cfg(feature = "profile")]
+        let _span = $crate::profile::Span::enter($name);
+        $body
+    }};
+}
+
+/// Times a phase until it's dropped, see `time_phase!`
+pub struct Span {
+    /** index of the phase in `PHASES` */
+    index: usize,
+    parent: Option<usize>,
+    start: Instant,
+}
+
+impl Span {
+    pub fn enter(name: &'static str) -> Self {
+        let parent = CURRENT.get();
+        // the phase is added on entering, so that phases are listed before the ones nested in them
+        let index = PHASES.with_borrow_mut(|phases| {
+            match phases
+                .iter()
+                .position(|phase| phase.name == name && phase.parent == parent)
+            {
+                Some(index) => index,
+                None => {
+                    phases.push(Phase {
+                        name,
+                        parent,
+                        depth: parent.map_or(0, |parent| phases[parent].depth + 1),
+                        calls: 0,
+                        total: Duration::ZERO,
+                    });
+                    phases.len() - 1
+                }
+            }
+        });
+        CURRENT.set(Some(index));
+        Self {
+            index,
+            parent,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        CURRENT.set(self.parent);
+        PHASES.with_borrow_mut(|phases| {
+            let phase = &mut phases[self.index];
+            phase.calls += 1;
+            phase.total += elapsed;
+        });
+    }
+}
+
+/// Take the phases recorded since the last call
+pub fn take_phases() -> Vec<Phase> {
+    PHASES.take()
+}
+
+/// The phases as indented lines like `  parse: 1.20ms (1 call)`, with every phase below the one
+/// it ran in
+pub fn report(phases: &[Phase]) -> String {
+    let mut report = String::new();
+    report_children(phases, None, &mut report);
+    report
+}
+
+fn report_children(phases: &[Phase], parent: Option<usize>, report: &mut String) {
+    for (index, phase) in phases.iter().enumerate() {
+        if phase.parent != parent {
+            continue;
+        }
+        let indent = "  ".repeat(phase.depth + 1);
+        let calls = match phase.calls {
+            1 => "1 call".to_string(),
+            calls => format!("{calls} calls"),
+        };
+        writeln!(
+            report,
+            "{indent}{}: {:.2?} ({calls})",
+            phase.name, phase.total
+        )
+        .unwrap();
+        report_children(phases, Some(index), report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases() {
+        for _ in 0..3 {
+            let _outer = Span::enter("outer");
+            for _ in 0..2 {
+                let _inner = Span::enter("inner");
+            }
+        }
+        let _other = Span::enter("other");
+        drop(_other);
+        // a phase with the same name and depth, but in another parent
+        for _ in 0..2 {
+            let _other = Span::enter("other");
+            let _inner = Span::enter("inner");
+        }
+        // a phase that first runs after other phases were added
+        let _outer = Span::enter("outer");
+        let _late = Span::enter("late");
+        drop((_late, _outer));
+
+        let phases = take_phases();
+        let summary: Vec<(&str, Option<usize>, usize, u32)> = phases
+            .iter()
+            .map(|phase| (phase.name, phase.parent, phase.depth, phase.calls))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("outer", None, 0, 4),
+                ("inner", Some(0), 1, 6),
+                ("other", None, 0, 3),
+                ("inner", Some(2), 1, 2),
+                ("late", Some(0), 1, 1),
+            ]
+        );
+        assert!(phases[0].total >= phases[1].total);
+        assert!(take_phases().is_empty());
+
+        let report = report(&phases);
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("  outer: ") && lines[0].ends_with(" (4 calls)"));
+        assert!(lines[1].starts_with("    inner: ") && lines[1].ends_with(" (6 calls)"));
+        assert!(lines[2].starts_with("    late: ") && lines[2].ends_with(" (1 call)"));
+        assert!(lines[3].starts_with("  other: ") && lines[3].ends_with(" (3 calls)"));
+        assert!(lines[4].starts_with("    inner: ") && lines[4].ends_with(" (2 calls)"));
+    }
+}
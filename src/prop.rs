@@ -0,0 +1,56 @@
+//! Minimal property-based testing: check a property against many randomly generated inputs, e.g.
+//! an optimised solver against a simple brute-force version. Every case is generated from its own
+//! seed, so a failing case can be rerun on its own with `PROP_SEED=<seed>`. The number of cases
+//! can be changed with `PROP_CASES`.
+
+use crate::random::Rng;
+use std::{
+    env,
+    fmt::Debug,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// Number of cases per property, if `PROP_CASES` isn't set
+pub const DEFAULT_CASES: u64 = 100;
+
+/// Run the property (which should panic on failure, e.g. with `assert_eq!`) against inputs made by
+/// `generate`. On failure the seed and the generated input are printed.
+pub fn check<T: Debug>(generate: impl Fn(&mut Rng) -> T, property: impl Fn(&T)) {
+    let seeds = match env_u64("PROP_SEED") {
+        Some(seed) => seed..seed + 1,
+        None => 0..env_u64("PROP_CASES").unwrap_or(DEFAULT_CASES),
+    };
+    for seed in seeds {
+        let input = generate(&mut Rng::new(seed));
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| property(&input))) {
+            eprintln!("property failed for seed {seed} (rerun with PROP_SEED={seed}), input:");
+            eprintln!("{input:#?}");
+            panic::resume_unwind(panic);
+        }
+    }
+}
+
+/// Read a number from an environment variable, for the settings of the test harnesses
+pub(crate) fn env_u64(name: &str) -> Option<u64> {
+    let value = env::var(name).ok()?;
+    Some(
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{name} should be a number, got '{value}'")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        check(|rng| rng.range(0..100), |n| assert!(*n < 100));
+
+        let failure = panic::catch_unwind(|| {
+            check(|rng| rng.range(0..100), |n| assert!(*n < 50));
+        });
+        assert!(failure.is_err());
+    }
+}
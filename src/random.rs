@@ -0,0 +1,83 @@
+//! Small seedable random number generator (SplitMix64), so that randomly generated puzzle inputs
+//! are reproducible from their seed without pulling in a dependency.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in the range. Panics if the range is empty.
+    pub fn range(&mut self, range: Range<usize>) -> usize {
+        assert!(!range.is_empty(), "can't pick from an empty range");
+        let len = (range.end - range.start) as u64;
+        // the modulo bias is negligible for the small ranges used here
+        range.start + (self.next_u64() % len) as usize
+    }
+
+    /// Random signed number in the range. Panics if the range is empty.
+    pub fn range_i64(&mut self, range: Range<i64>) -> i64 {
+        assert!(!range.is_empty(), "can't pick from an empty range");
+        let len = range.end.abs_diff(range.start);
+        range.start.wrapping_add((self.next_u64() % len) as i64)
+    }
+
+    /// True with a probability of `numerator / denominator`
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.next_u64() % denominator < numerator
+    }
+
+    /// Random element of the slice. Panics if the slice is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0..items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0..i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut rng = Rng::new(1234567);
+        // reference values of SplitMix64
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+
+        let numbers: Vec<usize> = (0..20).map(|_| Rng::new(7).range(3..10)).collect();
+        assert!(numbers.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = Rng::new(0);
+        for _ in 0..1000 {
+            assert!((3..10).contains(&rng.range(3..10)));
+            assert!((-5..5).contains(&rng.range_i64(-5..5)));
+        }
+        let mut items: Vec<u32> = (0..10).collect();
+        rng.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+}
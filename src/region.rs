@@ -0,0 +1,175 @@
+//! Region analysis on grids: flood fill, connected-component labelling and perimeters. Cells are
+//! connected to their four orthogonal neighbors.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::grid::{GridCoord, GridLike};
+
+/// Find all cells reachable from `seed` through passable cells, in the order they were reached.
+/// Returns an empty list if the seed itself isn't passable (or isn't in the grid).
+pub fn flood_fill<T>(
+    grid: &impl GridLike<T>,
+    seed: GridCoord,
+    passable: impl Fn(&T) -> bool,
+) -> Vec<GridCoord> {
+    let mut region: Vec<GridCoord> = vec![];
+    if !grid.cell(&seed).is_some_and(&passable) {
+        return region;
+    }
+
+    let mut visited: HashSet<GridCoord> = HashSet::from([seed]);
+    let mut queue: VecDeque<GridCoord> = VecDeque::from([seed]);
+    while let Some(coord) = queue.pop_front() {
+        region.push(coord);
+        for neighbor in coord.orthogonal_neighbors() {
+            if grid.cell(&neighbor).is_some_and(&passable) && visited.insert(neighbor) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    region
+}
+
+/// Result of labelling the connected regions of a grid
+#[derive(Debug, Clone, Default)]
+pub struct Regions {
+    /** region id of every passable cell */
+    pub labels: HashMap<GridCoord, usize>,
+    /** cells in each region, indexed by region id */
+    pub sizes: Vec<usize>,
+}
+
+impl Regions {
+    pub fn num_regions(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn region_of(&self, coord: &GridCoord) -> Option<usize> {
+        self.labels.get(coord).copied()
+    }
+
+    /// Coordinates of all cells in this region
+    pub fn cells(&self, region_id: usize) -> Vec<GridCoord> {
+        let mut cells: Vec<GridCoord> = self
+            .labels
+            .iter()
+            .filter(|(_, id)| **id == region_id)
+            .map(|(coord, _)| *coord)
+            .collect();
+        cells.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        cells
+    }
+
+    /// Perimeter of this region (see `perimeter`)
+    pub fn perimeter(&self, region_id: usize) -> usize {
+        perimeter(&self.cells(region_id))
+    }
+}
+
+/// Label the connected regions formed by the passable cells. Region ids are numbered from 0, in
+/// the row-major order of each region's first cell.
+pub fn label_regions<T>(grid: &impl GridLike<T>, passable: impl Fn(&T) -> bool) -> Regions {
+    let mut regions = Regions::default();
+    for coord in grid.coords() {
+        if regions.labels.contains_key(&coord) {
+            continue;
+        }
+        let region = flood_fill(grid, coord, &passable);
+        if region.is_empty() {
+            continue;
+        }
+        let region_id = regions.sizes.len();
+        regions.sizes.push(region.len());
+        regions
+            .labels
+            .extend(region.into_iter().map(|coord| (coord, region_id)));
+    }
+    regions
+}
+
+/// Number of cell edges on the border of a region, i.e. edges between a cell of the region and a
+/// cell outside of it (or the edge of the grid)
+pub fn perimeter(region: &[GridCoord]) -> usize {
+    let cells: HashSet<&GridCoord> = region.iter().collect();
+    cells
+        .iter()
+        .map(|coord| {
+            let inner_edges = coord
+                .orthogonal_neighbors()
+                .filter(|neighbor| cells.contains(neighbor))
+                .count();
+            4 - inner_edges
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{Grid, SparseGrid};
+
+    fn build_grid(rows: &[&str]) -> Grid<char> {
+        let mut grid = Grid::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, char) in row.chars().enumerate() {
+                grid.insert_cell((x, y).into(), char);
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let grid = build_grid(&["..#.", "..#.", "##..", "...#"]);
+        let is_open = |cell: &char| *cell == '.';
+
+        let mut region = flood_fill(&grid, (0, 0).into(), is_open);
+        region.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        let expected: Vec<GridCoord> =
+            vec![(0, 0).into(), (1, 0).into(), (0, 1).into(), (1, 1).into()];
+        assert_eq!(region, expected);
+        assert_eq!(flood_fill(&grid, (3, 0).into(), is_open).len(), 7);
+        assert!(flood_fill(&grid, (2, 0).into(), is_open).is_empty());
+        assert!(flood_fill(&grid, (9, 9).into(), is_open).is_empty());
+    }
+
+    #[test]
+    fn test_label_regions() {
+        let grid = build_grid(&["..#.", "..#.", "##..", "...#"]);
+        let regions = label_regions(&grid, |cell| *cell == '.');
+        assert_eq!(regions.sizes, vec![4, 7]);
+        assert_eq!(regions.region_of(&(3, 3).into()), None);
+        assert_eq!(regions.region_of(&(0, 3).into()), Some(1));
+        assert_eq!(regions.perimeter(0), 8);
+        assert_eq!(regions.perimeter(1), 16);
+
+        let walls = label_regions(&grid, |cell| *cell == '#');
+        assert_eq!(walls.sizes, vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_sparse_grid_regions() {
+        let mut grid: SparseGrid<bool> = SparseGrid::new();
+        for coord in [(5, 5), (6, 5), (6, 6), (100, 100)] {
+            grid.insert_cell(coord.into(), true);
+        }
+        grid.insert_cell((7, 6).into(), false);
+
+        let regions = label_regions(&grid, |cell| *cell);
+        assert_eq!(regions.sizes, vec![3, 1]);
+        assert_eq!(regions.perimeter(0), 8);
+        assert_eq!(flood_fill(&grid, (6, 6).into(), |cell| *cell).len(), 3);
+    }
+
+    #[test]
+    fn test_perimeter() {
+        assert_eq!(perimeter(&[]), 0);
+        assert_eq!(perimeter(&[(0, 0).into()]), 4);
+        // 3x3 ring: outer edges + the edges around the hole
+        let ring: Vec<GridCoord> = (0..3)
+            .flat_map(|y| (0..3).map(move |x| GridCoord { x, y }))
+            .filter(|coord| *coord != GridCoord { x: 1, y: 1 })
+            .collect();
+        assert_eq!(perimeter(&ring), 16);
+    }
+}
@@ -0,0 +1,236 @@
+//! Generic graph searches (BFS, Dijkstra, A*). The graph is never stored: it's described by a
+//! function returning the neighbors of a node, so these work on grids as well as implicit graphs.
+
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+    ops::Add,
+};
+
+/// Result of a successful search: the goal node that was reached, the cost to reach it, and the
+/// parent of every visited node so the path can be reconstructed.
+#[derive(Debug, Clone)]
+pub struct SearchResult<N, C> {
+    pub goal: N,
+    pub cost: C,
+    parents: HashMap<N, Option<N>>,
+}
+
+impl<N, C> SearchResult<N, C>
+where
+    N: Clone + Eq + Hash,
+{
+    /// Reconstruct the path from the start node to the goal (both included)
+    pub fn path(&self) -> Vec<N> {
+        let mut path = vec![self.goal.clone()];
+        while let Some(Some(parent)) = self.parents.get(path.last().expect("path is not empty")) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        path
+    }
+
+    /// Number of nodes that were visited during the search
+    pub fn num_visited(&self) -> usize {
+        self.parents.len()
+    }
+}
+
+/// Breadth-first search from one or more start nodes, until a node satisfying `is_goal` is found.
+/// All edges have the same cost, so the resulting cost is the number of steps taken.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbors: impl Fn(&N) -> I,
+    is_goal: impl Fn(&N) -> bool,
+) -> Option<SearchResult<N, usize>>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut parents: HashMap<N, Option<N>> = HashMap::new();
+    let mut queue: VecDeque<(N, usize)> = VecDeque::new();
+    for start in starts {
+        if parents.insert(start.clone(), None).is_none() {
+            queue.push_back((start, 0));
+        }
+    }
+
+    while let Some((node, steps)) = queue.pop_front() {
+        if is_goal(&node) {
+            return Some(SearchResult {
+                goal: node,
+                cost: steps,
+                parents,
+            });
+        }
+        for next_node in neighbors(&node) {
+            if let Entry::Vacant(entry) = parents.entry(next_node.clone()) {
+                entry.insert(Some(node.clone()));
+                queue.push_back((next_node, steps + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Dijkstra's shortest path from one or more start nodes, until a node satisfying `is_goal` is
+/// found. The neighbors function returns each neighbor together with the cost of the edge.
+pub fn dijkstra<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbors: impl Fn(&N) -> I,
+    is_goal: impl Fn(&N) -> bool,
+) -> Option<SearchResult<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(starts, neighbors, is_goal, |_| C::default())
+}
+
+/// A* search from one or more start nodes, until a node satisfying `is_goal` is found. The
+/// `heuristic` estimates the remaining cost to a goal and must never overestimate it, otherwise
+/// the result might not be the shortest path.
+pub fn astar<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbors: impl Fn(&N) -> I,
+    is_goal: impl Fn(&N) -> bool,
+    heuristic: impl Fn(&N) -> C,
+) -> Option<SearchResult<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut parents: HashMap<N, Option<N>> = HashMap::new();
+    let mut costs: HashMap<N, C> = HashMap::new();
+    let mut queue: BinaryHeap<QueueEntry<N, C>> = BinaryHeap::new();
+    for start in starts {
+        parents.insert(start.clone(), None);
+        costs.insert(start.clone(), C::default());
+        queue.push(QueueEntry {
+            estimate: heuristic(&start),
+            cost: C::default(),
+            node: start,
+        });
+    }
+
+    while let Some(QueueEntry { node, cost, .. }) = queue.pop() {
+        // skip stale entries, where a cheaper way to this node was found after it was queued
+        if costs.get(&node).is_some_and(|best_cost| cost > *best_cost) {
+            continue;
+        }
+        if is_goal(&node) {
+            return Some(SearchResult {
+                goal: node,
+                cost,
+                parents,
+            });
+        }
+        for (next_node, edge_cost) in neighbors(&node) {
+            let next_cost = cost + edge_cost;
+            let is_improvement = costs
+                .get(&next_node)
+                .is_none_or(|best_cost| next_cost < *best_cost);
+            if is_improvement {
+                costs.insert(next_node.clone(), next_cost);
+                parents.insert(next_node.clone(), Some(node.clone()));
+                queue.push(QueueEntry {
+                    estimate: next_cost + heuristic(&next_node),
+                    cost: next_cost,
+                    node: next_node,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Entry in the priority queue. Ordered so that the lowest estimate is popped first from the
+/// (max-)heap; the node itself doesn't need to be comparable.
+struct QueueEntry<N, C> {
+    estimate: C,
+    cost: C,
+    node: N,
+}
+
+impl<N, C: Ord> Ord for QueueEntry<N, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+impl<N, C: Ord> PartialOrd for QueueEntry<N, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N, C: Ord> PartialEq for QueueEntry<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N, C: Ord> Eq for QueueEntry<N, C> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small weighted graph: the direct edge 0 -> 3 is more expensive than going through 1 and 2
+    fn weighted_neighbors(node: &u32) -> Vec<(u32, u32)> {
+        match node {
+            0 => vec![(1, 1), (3, 10)],
+            1 => vec![(2, 2)],
+            2 => vec![(3, 3)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_bfs() {
+        // implicit graph on the integers: n -> n + 1 and n -> n * 2
+        let result = bfs([1_u32], |n| [n + 1, n * 2], |n| *n == 10).unwrap();
+        assert_eq!(result.cost, 4);
+        assert_eq!(result.path(), vec![1, 2, 4, 5, 10]);
+
+        let multi_source = bfs([3_u32, 9], |n| [n + 1], |n| *n == 10).unwrap();
+        assert_eq!(multi_source.cost, 1);
+        assert_eq!(multi_source.path(), vec![9, 10]);
+
+        assert!(bfs([0_u32], |n| (*n < 5).then_some(n + 1), |n| *n == 10).is_none());
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let result = dijkstra([0], weighted_neighbors, |n| *n == 3).unwrap();
+        assert_eq!(result.cost, 6);
+        assert_eq!(result.path(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_astar() {
+        // shortest path on an open 10x10 grid, with the manhattan distance as heuristic
+        let goal = (9_i32, 9_i32);
+        let result = astar(
+            [(0_i32, 0_i32)],
+            |&(x, y)| {
+                [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                    .into_iter()
+                    .filter(|(x, y)| (0..10).contains(x) && (0..10).contains(y))
+                    .map(|node| (node, 1))
+            },
+            |node| *node == goal,
+            |&(x, y)| goal.0.abs_diff(x) + goal.1.abs_diff(y),
+        )
+        .unwrap();
+        assert_eq!(result.cost, 18);
+        assert_eq!(result.path().len(), 19);
+        assert!(result.num_visited() < 100);
+    }
+}
@@ -0,0 +1,51 @@
+//! The solvers of every day accept a generated input (see `advent_of_code::generate`). The
+//! solvers live in the day binaries, so each binary is run in a directory whose `src/inputs`
+//! holds the generated input.
+
+use advent_of_code::generate::generate;
+use std::{env, fs, process::Command};
+
+/// The binary of each day, and the size of its input. Day 11 plays 10000 rounds for every item
+/// in part two, so it gets fewer monkeys.
+const DAYS: &[(u8, &str, usize)] = &[
+    (1, env!("CARGO_BIN_EXE_01"), 100),
+    (2, env!("CARGO_BIN_EXE_02"), 100),
+    (3, env!("CARGO_BIN_EXE_03"), 100),
+    (4, env!("CARGO_BIN_EXE_04"), 100),
+    (5, env!("CARGO_BIN_EXE_05"), 100),
+    (6, env!("CARGO_BIN_EXE_06"), 100),
+    (7, env!("CARGO_BIN_EXE_07"), 100),
+    (8, env!("CARGO_BIN_EXE_08"), 100),
+    (9, env!("CARGO_BIN_EXE_09"), 100),
+    (10, env!("CARGO_BIN_EXE_10"), 100),
+    (11, env!("CARGO_BIN_EXE_11"), 10),
+    (12, env!("CARGO_BIN_EXE_12"), 100),
+    (13, env!("CARGO_BIN_EXE_13"), 100),
+    (14, env!("CARGO_BIN_EXE_14"), 100),
+];
+
+#[test]
+fn test_generated_inputs() {
+    for &(day, binary, size) in DAYS {
+        let dir = env::temp_dir().join(format!(
+            "advent_of_code-{}-day-{day:02}",
+            std::process::id()
+        ));
+        let inputs = dir.join("src/inputs");
+        fs::create_dir_all(&inputs).unwrap();
+        fs::write(
+            inputs.join(format!("{day:02}.txt")),
+            generate(day, size, 1).unwrap(),
+        )
+        .unwrap();
+        let output = Command::new(binary).current_dir(&dir).output().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success() && !stdout.contains("error:") && !stdout.contains("not solved"),
+            "day {day}:\n{stdout}{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}